@@ -13,6 +13,17 @@ pub struct PdfData {
     pub verbose : Verbose,
     pub recursive : bool,
     pub path : String,
+    pub password : Option<String>,
+    pub revisions : bool, // report the Info metadata of every revision
+    pub notes_format : Option<NotesFormat>, // export the annotations of the pdfs
+    pub attachments : bool, // report embedded files, and read the metadata of attached pdfs
+    pub extract_dir : Option<String>, // directory to save embedded files in
+}
+
+#[derive(PartialEq, Debug)]
+pub enum NotesFormat {
+    Markdown,
+    Json,
 }
 
 #[derive(PartialEq, Debug)]
@@ -48,6 +59,11 @@ pub fn parse_args(args : &Vec<String>) -> Option<PdfData>{
         verbose : Verbose::Default,
         path : args[1].to_string(),
         timeouts : 0,
+        password : None,
+        revisions : false,
+        notes_format : None,
+        attachments : false,
+        extract_dir : None,
     };
 
     let mut arg_it : usize = 2;
@@ -80,6 +96,34 @@ pub fn parse_args(args : &Vec<String>) -> Option<PdfData>{
             "-rec" | "-recursive" => {
                 pdf_data.recursive = true;
             }
+            "-p" | "-password" => {
+                arg_it += 1;
+                let Some(next_arg) = args.get(arg_it) else {
+                    println!("No argument given for password");
+                    println!("Use -help to show available argument options");
+                    return None;
+                };
+                pdf_data.password = Some(next_arg.to_string());
+            }
+            "-rev" | "-revisions" => {
+                pdf_data.revisions = true;
+            }
+            "-a" | "-annotations" => {
+                parse_notes_format(&mut arg_it, &mut pdf_data, args)?;
+            }
+            "-att" | "-attachments" => {
+                pdf_data.attachments = true;
+            }
+            "-x" | "-extract" => {
+                arg_it += 1;
+                let Some(next_arg) = args.get(arg_it) else {
+                    println!("No argument given for extract");
+                    println!("Use -help to show available argument options");
+                    return None;
+                };
+                pdf_data.attachments = true;
+                pdf_data.extract_dir = Some(next_arg.to_string());
+            }
             _ => {
                 println!("Unknown argument given: {}", args[arg_it]);
                 println!("Metaquill usage: ./metaquill [pdf filepath] [arguments]");
@@ -102,10 +146,16 @@ fn print_help(){
     println!("\t-v | -verbose — choose verbose to run [light | default | full]");
     println!("\t-o | -output — set path for json output file (default = 'output.json')");
     println!("\t-rec | -recursive — search subdirectories if encountered");
+    println!("\t-p | -password — password used to decrypt encrypted pdfs");
+    println!("\t-rev | -revisions — report the info metadata of every revision of incrementally updated pdfs");
+    println!("\t-a | -annotations — export highlights and comments to annotations.md or annotations.json [md | json]");
+    println!("\t-att | -attachments — report embedded files, and the metadata of attached pdfs");
+    println!("\t-x | -extract — save embedded files to the given directory");
+    println!("Use '-' as filepath to read a pdf from stdin");
 }
 
 /// Parses argument for -reader
-fn parse_reader(it : &mut usize, pdf_data : &mut PdfData, args : &Vec<String>) -> Option<()>{
+fn parse_reader(it : &mut usize, pdf_data : &mut PdfData, args : &[String]) -> Option<()>{
     // Find next arg
     *it += 1;
     let Some(next_arg) = args.get(*it) else {
@@ -131,8 +181,35 @@ fn parse_reader(it : &mut usize, pdf_data : &mut PdfData, args : &Vec<String>) -
     return Some(());
 }
 
+/// Parses argument for -annotations
+fn parse_notes_format(it : &mut usize, pdf_data : &mut PdfData, args : &[String]) -> Option<()>{
+    // Find next arg
+    *it += 1;
+    let Some(next_arg) = args.get(*it) else {
+        println!("No argument given for annotations");
+        println!("Use -help to show available argument options");
+        return None;
+    };
+    
+    match next_arg.as_str() {
+        "md" | "markdown" => {
+            pdf_data.notes_format = Some(NotesFormat::Markdown);
+        }
+        "json" => {
+            pdf_data.notes_format = Some(NotesFormat::Json);
+        }
+        _ => {
+            println!("Invalid argument for annotations: {}", next_arg);
+            println!("Use -help to show available argument options");
+            return None;
+        }
+    }
+    
+    return Some(());
+}
+
 /// Parses argument for -verbose
-fn parse_verbose(it : &mut usize, pdf_data : &mut PdfData, args : &Vec<String>) -> Option<()>{
+fn parse_verbose(it : &mut usize, pdf_data : &mut PdfData, args : &[String]) -> Option<()>{
     // Find next arg
     *it += 1;
     let Some(next_arg) = args.get(*it) else {
@@ -62,6 +62,35 @@ fn extract_authors(json: &Value) -> String {
         .unwrap_or_else(|| "Unknown Authors".to_string())
 }
 
+/// Creates the metadata from a Crossref work
+fn work_to_metadata(work: &Value, title_confidence: f64) -> PdfMetadata {
+    PdfMetadata {
+        title: extract_array_str(work, "title"),
+        doi: extract_str(work, "DOI"),
+        score: work["score"].as_f64().unwrap_or(0.0),
+        authors: extract_authors(work),
+        publisher: extract_str(work, "publisher"),
+        journal: extract_array_str(work, "container-title"),
+        year: work["published-print"]["date-parts"]
+            .get(0)
+            .and_then(|arr| arr.get(0))
+            .and_then(Value::as_i64)
+            .or_else(|| {
+                work["published-online"]["date-parts"]
+                    .get(0)
+                    .and_then(|arr| arr.get(0))
+                    .and_then(Value::as_i64)
+            })
+            .unwrap_or(0),
+        volume: extract_str(work, "volume"),
+        issue: extract_str(work, "issue"),
+        pages: extract_str(work, "page"),
+        issn: extract_array_str(work, "ISSN"),
+        url: extract_str(work, "URL"),
+        title_confidence,
+    }
+}
+
 /// Use levenshein distance formula to determine similarity between strings
 pub fn compare_results(result_title: &str, search_title: &str) -> f64 {
     // Normalize: lowercase and trim both strings
@@ -135,6 +164,17 @@ pub async fn call(pdf_metadata: &PdfStruct, pdf_data : &PdfData) -> Result<Optio
         }
     }
     
+    // A DOI from a link identifies the work, the title search is only a fallback
+    if let Some(doi) = &pdf_metadata.link_doi {
+        let request_url = format!("https://api.crossref.org/works/{}", encode(doi));
+        if pdf_data.verbose == Verbose::Full {
+            println!("🔍 API Request URL: {}", request_url);
+        }
+        if let Ok(json) = fetch_with_retry(&request_url).await {
+            return Ok(Some(work_to_metadata(&json["message"], 100.0)));
+        }
+    }
+
     // to store top result
     let mut top_result : Option<PdfMetadata> = None;
 
@@ -191,32 +231,7 @@ pub async fn call(pdf_metadata: &PdfStruct, pdf_data : &PdfData) -> Result<Optio
             for work in items {
                 let result_title = extract_array_str(work, "title");
                 let title_confidence = compare_results(&result_title, title_raw);
-    
-                let metadata = PdfMetadata {
-                    title: result_title,
-                    doi: extract_str(work, "DOI"),
-                    score: work["score"].as_f64().unwrap_or(0.0),
-                    authors: extract_authors(work),
-                    publisher: extract_str(work, "publisher"),
-                    journal: extract_array_str(work, "container-title"),
-                    year: work["published-print"]["date-parts"]
-                        .get(0)
-                        .and_then(|arr| arr.get(0))
-                        .and_then(Value::as_i64)
-                        .or_else(|| {
-                            work["published-online"]["date-parts"]
-                                .get(0)
-                                .and_then(|arr| arr.get(0))
-                                .and_then(Value::as_i64)
-                        })
-                        .unwrap_or(0),
-                    volume: extract_str(work, "volume"),
-                    issue: extract_str(work, "issue"),
-                    pages: extract_str(work, "page"),
-                    issn: extract_array_str(work, "ISSN"),
-                    url: extract_str(work, "URL"),
-                    title_confidence: title_confidence,
-                };
+                let metadata = work_to_metadata(work, title_confidence);
     
                 metadata_list.push(metadata);
             }
@@ -1,10 +1,9 @@
 use std::error::Error;
 use lopdf::Document;
-use tag_pdf_to_text::load_pdf_doc;
 use tokio::runtime::Runtime;
 use crate::arg_parser::{Verbose, PdfData};
-use crate::file_manager::load_pdf;
-use crate::metadata::{extract_metadata, fetch_metadata, PdfStruct};
+use crate::file_manager::{load_pdf, load_tag_pdf};
+use crate::metadata::{extract_attachments, extract_metadata, extract_revisions, fetch_metadata, pages_match, AttachmentInfo, PdfStruct};
 use crate::call::{call, PdfMetadata};
 
 /// Reads metadata from pdf
@@ -21,8 +20,8 @@ fn read_pdf_metadata (filepath: &str) -> Option<PdfStruct>{
 
 /// Validates metadata through an API call
 fn get_api_metadata(pdf_obj : &PdfStruct, pdf_data : &mut PdfData) -> Result<PdfMetadata, Box<dyn Error>>{
-    // If no title exist, no call is made
-    if pdf_obj.assumed_title.is_empty() && pdf_obj.metadata_title.is_empty(){
+    // If no title or DOI exist, no call is made
+    if pdf_obj.assumed_title.is_empty() && pdf_obj.metadata_title.is_empty() && pdf_obj.link_doi.is_none(){
         return Err("No title found in PDF".into());
     }
     
@@ -76,10 +75,73 @@ pub fn read_pdf_dir(pdf_paths : &Vec<String>, pdf_data : &mut PdfData){
 
 /// Reads a pdf with the tag-to-pdf library
 fn tag_read_pdf(filepath: &str, pdf_data : &mut PdfData){
-    match load_pdf_doc(filepath) {
+    match load_tag_pdf(filepath) {
         Ok(mut pdf) => {
+            if pdf.is_recovered() && pdf_data.verbose != Verbose::Light {
+                println!("Broken xref table, recovered by scanning the document");
+            }
+
+            // Encrypted pdfs without an empty user password need the given password
+            if pdf.is_locked() {
+                let unlocked = match &pdf_data.password {
+                    Some(password) => pdf.unlock(password),
+                    None => false,
+                };
+                if !unlocked {
+                    println!("Encrypted pdf, password is missing or incorrect");
+                }
+            }
+
             let mut pdf_meta = extract_metadata(&mut pdf, filepath);
 
+            // Print the problems that were worked around
+            if pdf_data.verbose == Verbose::Full {
+                for warning in pdf.warnings() {
+                    println!("Warning: {}", warning);
+                }
+            }
+
+            // Read the info metadata of every revision
+            if pdf_data.revisions {
+                pdf_meta.revisions = extract_revisions(&mut pdf);
+                if pdf_data.verbose != Verbose::Light {
+                    for rev in &pdf_meta.revisions {
+                        println!("Revision {}: {} changed objects", rev.revision, rev.changed_objects);
+                        for (key, value) in &rev.info {
+                            println!("\t{} = {}", key, value);
+                        }
+                    }
+                }
+            }
+
+            // Read embedded files
+            if pdf_data.attachments {
+                pdf_meta.attachments = extract_attachments(&mut pdf, pdf_data.extract_dir.as_deref(), 0);
+                if pdf_data.verbose != Verbose::Light {
+                    print_attachments(&pdf_meta.attachments, 0);
+                }
+            }
+
+            // Print signatures
+            if pdf_data.verbose != Verbose::Light {
+                for field in &pdf_meta.signatures {
+                    let Some(signature) = &field.signature else {
+                        continue;
+                    };
+                    let signer = signature.signer.as_deref().unwrap_or("unknown signer");
+                    if signature.covers_whole_file {
+                        println!("Signed by {}", signer);
+                    } else {
+                        println!("Signed by {}, the file was changed after signing", signer);
+                    }
+                }
+            }
+
+            // Read highlights and comments
+            if pdf_data.notes_format.is_some() {
+                pdf_meta.notes = pdf.get_notes();
+            }
+
             // Print title info
             if pdf_data.verbose == Verbose::Full {
                 println!("MetaTitle = {}", pdf_meta.metadata_title);
@@ -97,6 +159,13 @@ fn tag_read_pdf(filepath: &str, pdf_data : &mut PdfData){
                     if pdf_data.verbose != Verbose::Light {
                         println!("Confidence score: {:.0}", x.title_confidence);
                     }
+
+                    // The printed page numbers should be the pages of the article
+                    if pages_match(&pdf_meta, &x.pages) == Some(false) && pdf_data.verbose != Verbose::Light {
+                        if let Some((first, last)) = &pdf_meta.printed_pages {
+                            println!("Printed pages {}-{} do not match pages {} from the API", first, last, x.pages);
+                        }
+                    }
                     pdf_meta.api_metadata = Some(x);
                     pdf_data.api_hits += 1;
 
@@ -108,12 +177,26 @@ fn tag_read_pdf(filepath: &str, pdf_data : &mut PdfData){
             pdf_data.pdfs.push(pdf_meta);
         }
         Err(e) =>{
-            println!("Pdf Loading Error: {:?}", e);
+            println!("Pdf Loading Error: {}", e);
             pdf_data.fails += 1;
         }
     };
 }
 
+/// Prints the embedded files, and the titles of attached PDFs
+fn print_attachments(attachments : &Vec<AttachmentInfo>, depth : usize){
+    let indent = "\t".repeat(depth);
+    for attachment in attachments {
+        println!("{}Attachment: {} ({} bytes)", indent, attachment.name, attachment.size);
+        let Some(meta) = &attachment.metadata else {
+            continue;
+        };
+        println!("{}\tMetaTitle = {}", indent, meta.metadata_title);
+        println!("{}\tAssumedTitle = {}", indent, meta.assumed_title);
+        print_attachments(&meta.attachments, depth+1);
+    }
+}
+
 /// Reads a pdf with the lopdf library
 fn lo_read_pdf(filepath: &str, pdf_data : &mut PdfData){
     // LOPDF
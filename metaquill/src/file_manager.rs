@@ -1,30 +1,48 @@
-use crate::{arg_parser::PdfData, metadata::PdfStruct};
-use std::fs::read_dir;
+use crate::{arg_parser::{NotesFormat, PdfData}, metadata::{pages_match, AttachmentInfo, PdfStruct, RevisionInfo}};
+use std::fs::{create_dir_all, read_dir, OpenOptions};
 use std::{fs::File, path::Path};
-use std::io::Write;
+use std::io::{self, Write};
 use serde_json::{json, Value};
 use lopdf::{Document, Error as LoError};
+use tag_pdf_to_text::{annotation::Note, document, form::FormField, info::DocumentInfo, load_pdf_doc};
 use std::error::Error;
 
+// Filepath used to read a pdf from stdin
+pub const STDIN_PATH : &str = "-";
+
 pub fn load_pdf(filepath : &str) -> Result<Document, LoError> {
+    if filepath == STDIN_PATH {
+        return Document::load_from(io::stdin());
+    }
     let document = Document::load(filepath)?;
     return Ok(document);
 }
 
+/// Loads a pdf with the tag-to-pdf library, from a file or from stdin
+pub fn load_tag_pdf(filepath : &str) -> Result<document::Document, document::PdfError> {
+    if filepath == STDIN_PATH {
+        return document::Document::from_reader(io::stdin());
+    }
+    load_pdf_doc(filepath)
+}
+
 // Returns a list of filepaths of all pdf documents in given directory
 // Use rec = true for search in subdirectories
 pub fn get_pdf_paths(filepath : &str, rec : bool) -> Option<Vec<String>> {
     let mut pdf_paths : Vec<String> = Vec::new();
     let path = Path::new(filepath);
 
-    if path.is_dir(){
+    if filepath == STDIN_PATH {
+        // Pdf is read from stdin
+        pdf_paths.push(filepath.to_string());
+    } else if path.is_dir(){
         // If path is a directory
         read_directory(path, &mut pdf_paths, rec);
     } else if path.is_file() {
         // If path is a file
         read_file_path(path, &mut pdf_paths);
     } else {
-        println!("The file path has to be a valid pdf file or a directory");
+        println!("The file path has to be a valid pdf file, a directory or '{}' for stdin", STDIN_PATH);
         return None;
     }
     return Some(pdf_paths);
@@ -86,7 +104,7 @@ pub fn export_json(pdf_data : &mut PdfData) -> Result<(), Box <dyn Error>> {
     for pdf in pdfs{
         if let Some(api_meta) = &pdf.api_metadata {
             // If api metadata exist
-            let json_value = json!({
+            let mut json_value = json!({
                 "file_name": pdf.filename,
                 "title": api_meta.title,
                 "title_confidence": api_meta.title_confidence,
@@ -101,7 +119,22 @@ pub fn export_json(pdf_data : &mut PdfData) -> Result<(), Box <dyn Error>> {
                 "pages": api_meta.pages,
                 "issn": api_meta.issn,
                 "url": api_meta.url,
+                "page_count": pdf.page_count,
+                "printed_pages": printed_pages(pdf),
+                "pages_match": pages_match(pdf, &api_meta.pages),
             });
+            if let Some(info) = &pdf.info {
+                json_value["info"] = info_json(info);
+            }
+            if !pdf.revisions.is_empty() {
+                json_value["revisions"] = revisions_json(&pdf.revisions);
+            }
+            if !pdf.attachments.is_empty() {
+                json_value["attachments"] = attachments_json(&pdf.attachments);
+            }
+            if !pdf.signatures.is_empty() {
+                json_value["signatures"] = signatures_json(&pdf.signatures);
+            }
             json_data.push(json_value);
         } else {
             // If no api metadata is found
@@ -129,22 +162,46 @@ pub fn export_json(pdf_data : &mut PdfData) -> Result<(), Box <dyn Error>> {
                 }
             }
 
-            let json_value = json!({
+            // DOI, journal and year from the XMP metadata, with the DOI of a link and the year from the Info object as fallback
+            let xmp = pdf.xmp.as_ref();
+            let json_doi = xmp.and_then(|x| x.doi.clone()).or(pdf.link_doi.clone());
+            let json_journal = xmp.and_then(|x| x.publication_name.clone());
+            let mut json_year = xmp.and_then(|x| x.date.as_ref()).and_then(|x| x.get(0..4)).and_then(|x| x.parse::<i64>().ok());
+
+            // Without a year, the creation date of the file is a hint
+            if json_year.is_none() {
+                json_year = pdf.info.as_ref().and_then(|x| x.creation_date).map(|x| x.year as i64);
+            }
+
+            let mut json_value = json!({
                 "file_name": pdf.filename,
                 "title": json_title,
                 "title_confidence": null,
                 "authors": json_authors,
-                "doi": null,
+                "doi": json_doi,
                 "api_score": null,
                 "publisher": null,
-                "journal": null,
-                "year": null,
+                "journal": json_journal,
+                "year": json_year,
                 "volume": null,
                 "issue": null,
-                "pages": null,
+                "pages": printed_pages(pdf),
                 "issn": null,
                 "url": null,
+                "page_count": pdf.page_count,
             });
+            if let Some(info) = &pdf.info {
+                json_value["info"] = info_json(info);
+            }
+            if !pdf.revisions.is_empty() {
+                json_value["revisions"] = revisions_json(&pdf.revisions);
+            }
+            if !pdf.attachments.is_empty() {
+                json_value["attachments"] = attachments_json(&pdf.attachments);
+            }
+            if !pdf.signatures.is_empty() {
+                json_value["signatures"] = signatures_json(&pdf.signatures);
+            }
             json_data.push(json_value);
         }
     }
@@ -172,6 +229,44 @@ pub fn export_json(pdf_data : &mut PdfData) -> Result<(), Box <dyn Error>> {
     Ok(())
 }
 
+// Max number added to the name of an attachment, when files with the same name exist
+const MAX_NAME_SUFFIX : usize = 10000;
+
+/// Saves an embedded file in a directory. Existing files are kept, a number is added to the name instead, file_1.pdf
+pub fn save_attachment(dir : &str, name : &str, data : &[u8]) -> Result<(), Box <dyn Error>> {
+    if let Err(err) = create_dir_all(dir) {
+        let err_msg = format!("Failed to create directory {}: {}", dir, err);
+        return Err(err_msg.into());
+    }
+
+    // Only use the file name, names can contain paths
+    let fname = split_name(name).filter(|x| !x.is_empty() && x != "." && x != "..").unwrap_or("attachment".to_string());
+    let stem = Path::new(&fname).file_stem().map(|x| x.to_string_lossy().to_string()).unwrap_or(fname.clone());
+    let extension = Path::new(&fname).extension().map(|x| format!(".{}", x.to_string_lossy())).unwrap_or_default();
+
+    // Attachments of different PDFs, or of an attached PDF and its parent, can have the same name
+    let mut filepath = Path::new(dir).join(&fname);
+    let mut suffix : usize = 0;
+    let mut file = loop {
+        match OpenOptions::new().write(true).create_new(true).open(&filepath) {
+            Ok(x) => break x,
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists && suffix < MAX_NAME_SUFFIX => {
+                suffix += 1;
+                filepath = Path::new(dir).join(format!("{}_{}{}", stem, suffix, extension));
+            }
+            Err(err) => {
+                let err_msg = format!("Failed to create file {:?}: {}", filepath, err);
+                return Err(err_msg.into());
+            }
+        }
+    };
+    if let Err(err) = file.write_all(data){
+        let err_msg = format!("Failed to write to file {:?}: {}", filepath, err);
+        return Err(err_msg.into());
+    };
+    Ok(())
+}
+
 /// Get last part (filename) of filepath
 pub fn split_name(filepath: &str) -> Option<String>{
     // Split by slash and take the last part
@@ -239,3 +334,224 @@ pub fn export_csv(pdfs : &mut Vec<PdfStruct>) -> Result<(), Box <dyn Error>>{
     }
     Ok(())
 }
+
+/// Export the highlights and comments of the read PDFs, as markdown or json
+pub fn export_notes(pdf_data : &PdfData) -> Result<(), Box <dyn Error>> {
+    let Some(format) = &pdf_data.notes_format else {
+        return Ok(());
+    };
+
+    let mut markdown = String::new();
+    let mut json_data : Vec<Value> = Vec::new();
+    for pdf in &pdf_data.pdfs {
+        if pdf.notes.is_empty() {
+            continue;
+        }
+        let title = get_title(pdf);
+        let doi = get_doi(pdf);
+        match format {
+            NotesFormat::Markdown => {
+                markdown.push_str(&notes_markdown(pdf, &title, &doi));
+            }
+            NotesFormat::Json => {
+                let notes : Vec<Value> = pdf.notes.iter().map(note_json).collect();
+                json_data.push(json!({
+                    "file_name": pdf.filename,
+                    "title": title,
+                    "doi": doi,
+                    "notes": notes,
+                }));
+            }
+        }
+    }
+
+    // Content and name of the notes file
+    let (filepath, content) = match format {
+        NotesFormat::Markdown => ("annotations.md", markdown),
+        NotesFormat::Json => {
+            let Ok(json_str) = serde_json::to_string_pretty(&Value::Array(json_data)) else {
+                return Err("Failed to create json".into());
+            };
+            ("annotations.json", json_str)
+        }
+    };
+
+    let mut file = match File::create(filepath) {
+        Ok(x) => x,
+        Err(err) => {
+            let err_msg = format!("Failed to create annotations file: {}", err);
+            return Err(err_msg.into());
+        }
+    };
+    if let Err(err) = file.write_all(content.as_bytes()){
+        let err_msg = format!("Failed to write to annotations file: {}", err);
+        return Err(err_msg.into());
+    };
+    Ok(())
+}
+
+/// Returns the title of a PDF, from the API call, the metadata or the text
+fn get_title(pdf : &PdfStruct) -> Option<String> {
+    if let Some(api_meta) = &pdf.api_metadata {
+        return Some(api_meta.title.clone());
+    }
+    if !pdf.metadata_title.is_empty() {
+        return Some(pdf.metadata_title.clone());
+    }
+    if !pdf.assumed_title.is_empty() {
+        return Some(pdf.assumed_title.clone());
+    }
+    None
+}
+
+/// Returns the DOI of a PDF, from the API call, the XMP metadata or a link
+fn get_doi(pdf : &PdfStruct) -> Option<String> {
+    if let Some(api_meta) = &pdf.api_metadata {
+        return Some(api_meta.doi.clone());
+    }
+    pdf.xmp.as_ref().and_then(|x| x.doi.clone()).or(pdf.link_doi.clone())
+}
+
+/// Creates the markdown section with the notes of a PDF
+fn notes_markdown(pdf : &PdfStruct, title : &Option<String>, doi : &Option<String>) -> String {
+    let mut section = String::new();
+    section.push_str(&format!("# {}\n\n", title.as_deref().unwrap_or(&pdf.filename)));
+    if let Some(doi) = doi {
+        section.push_str(&format!("DOI: {}  \n", doi));
+    }
+    section.push_str(&format!("File: {}\n\n", pdf.filename));
+
+    // - **Highlight** (p. 3, author, date): "marked text"
+    for note in &pdf.notes {
+        let mut details = vec![format!("p. {}", note.page_index + 1)];
+        if let Some(author) = &note.author {
+            details.push(author.clone());
+        }
+        if let Some(modified) = &note.modified {
+            details.push(modified.to_string());
+        }
+        section.push_str(&format!("- **{}** ({})", note.subtype, details.join(", ")));
+        if let Some(marked_text) = &note.marked_text {
+            section.push_str(&format!(": \"{}\"", marked_text));
+        }
+        section.push('\n');
+
+        // The comment, indented under the list item
+        if let Some(contents) = &note.contents {
+            for line in contents.lines() {
+                section.push_str(&format!("  {}\n", line));
+            }
+        }
+    }
+    section.push('\n');
+    section
+}
+
+/// Creates the json object for a note
+fn note_json(note : &Note) -> Value {
+    json!({
+        "type": note.subtype,
+        "page": note.page_index + 1,
+        "author": note.author,
+        "modified": note.modified.map(|x| x.to_string()),
+        "marked_text": note.marked_text,
+        "contents": note.contents,
+    })
+}
+
+/// Returns the printed page range of a PDF, such as 231-245
+fn printed_pages(pdf : &PdfStruct) -> Option<String> {
+    let (first, last) = pdf.printed_pages.as_ref()?;
+    if first == last {
+        return Some(first.clone());
+    }
+    Some(format!("{}-{}", first, last))
+}
+
+/// Creates the json object for the Info dictionary
+fn info_json(info : &DocumentInfo) -> Value {
+    let mut json_info = json!({
+        "title": info.title,
+        "author": info.author,
+        "subject": info.subject,
+        "keywords": info.keywords,
+        "creator": info.creator,
+        "producer": info.producer,
+        "creation_date": info.creation_date.map(|x| x.to_string()),
+        "mod_date": info.mod_date.map(|x| x.to_string()),
+        "trapped": info.trapped,
+    });
+    if !info.custom.is_empty() {
+        json_info["custom"] = json!(info.custom);
+    }
+    json_info
+}
+
+/// Creates the json array for the embedded files, with the metadata of attached PDFs
+fn attachments_json(attachments : &Vec<AttachmentInfo>) -> Value {
+    let mut json_attachments : Vec<Value> = Vec::new();
+    for attachment in attachments {
+        let mut json_value = json!({
+            "name": attachment.name,
+            "mime_type": attachment.mime_type,
+            "size": attachment.size,
+            "checksum": attachment.checksum,
+            "creation_date": attachment.creation_date,
+            "page": attachment.page,
+        });
+        if let Some(meta) = &attachment.metadata {
+            let mut json_meta = json!({
+                "title": get_title(meta),
+                "authors": meta.author,
+                "doi": get_doi(meta),
+                "page_count": meta.page_count,
+            });
+            if !meta.attachments.is_empty() {
+                json_meta["attachments"] = attachments_json(&meta.attachments);
+            }
+            json_value["metadata"] = json_meta;
+        }
+        json_attachments.push(json_value);
+    }
+    Value::Array(json_attachments)
+}
+
+/// Creates the json array for the signatures of signature fields
+fn signatures_json(fields : &Vec<FormField>) -> Value {
+    let mut json_signatures : Vec<Value> = Vec::new();
+    for field in fields {
+        let Some(signature) = &field.signature else {
+            continue;
+        };
+        let byte_range : Vec<[usize; 2]> = signature.byte_range.iter().map(|(offset, length)| [*offset, *length]).collect();
+        json_signatures.push(json!({
+            "field": field.name,
+            "signer": signature.signer,
+            "signing_time": signature.signing_time.map(|x| x.to_string()),
+            "reason": signature.reason,
+            "location": signature.location,
+            "contact_info": signature.contact_info,
+            "sub_filter": signature.sub_filter,
+            "byte_range": byte_range,
+            "covers_whole_file": signature.covers_whole_file,
+        }));
+    }
+    Value::Array(json_signatures)
+}
+
+/// Creates the json array for the info metadata of each revision
+fn revisions_json(revisions : &Vec<RevisionInfo>) -> Value {
+    let mut json_revisions : Vec<Value> = Vec::new();
+    for rev in revisions {
+        let mut info = serde_json::Map::new();
+        for (key, value) in &rev.info {
+            info.insert(key.clone(), Value::String(value.clone()));
+        }
+        json_revisions.push(json!({
+            "revision": rev.revision,
+            "changed_objects": rev.changed_objects,
+            "info": info,
+        }));
+    }
+    Value::Array(json_revisions)
+}
@@ -2,7 +2,7 @@
 use std::env;
 use arg_parser::parse_args;
 use document::read_pdf_dir;
-use file_manager::{export_csv, export_json, get_pdf_paths};
+use file_manager::{export_csv, export_json, export_notes, get_pdf_paths};
 mod metadata;
 mod call;
 mod file_manager;
@@ -36,4 +36,9 @@ fn main() {
     if let Err(err) = export_csv(&mut pdf_data.pdfs){
         println!("{}", err);
     };
+
+    // Output highlights and comments
+    if let Err(err) = export_notes(&pdf_data){
+        println!("{}", err);
+    };
 }
@@ -1,9 +1,17 @@
 use lopdf::{Document, Object,content::Content};
 use regex::Regex;
+use std::sync::LazyLock;
 use encoding_rs::WINDOWS_1252;
 use tag_pdf_to_text::document;
+use tag_pdf_to_text::xmp::XmpMetadata;
+use tag_pdf_to_text::info::DocumentInfo;
+use tag_pdf_to_text::annotation::Note;
+use tag_pdf_to_text::form::FormField;
 
-use crate::{call::PdfMetadata, file_manager::split_name};
+use crate::{call::PdfMetadata, file_manager::{save_attachment, split_name}};
+
+// A DOI, 10.xxxx/suffix. Compiled once, it is matched against every link and page
+static DOI_REGEX : LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"10\.\d{4,9}/[^\s"<>#?]+"#).unwrap());
 
 struct TextObject{
     font_size: f32,
@@ -18,9 +26,44 @@ pub struct PdfStruct {
     pub metadata_title: String,
     pub assumed_title: String,
     pub author: Vec<String>,
+    pub page_count : usize,
+    pub printed_pages : Option<(String, String)>, // Page labels of the first and last page
+    pub revisions : Vec<RevisionInfo>,
+    pub xmp : Option<XmpMetadata>,
+    pub info : Option<DocumentInfo>,
+    pub link_doi : Option<String>, // DOI from a link annotation on the first page
+    pub notes : Vec<Note>, // Highlights and comments
+    pub attachments : Vec<AttachmentInfo>, // Embedded files
+    pub signatures : Vec<FormField>, // Signed signature fields
     pub api_metadata : Option<PdfMetadata>,
 }
 
+/// Info metadata of one revision of the PDF
+#[derive(Debug)]
+pub struct RevisionInfo {
+    pub revision : usize,
+    pub changed_objects : usize,
+    pub info : Vec<(String, String)>, // (key, value) for the keys that are set
+}
+
+/// A file embedded in the PDF
+#[derive(Debug)]
+pub struct AttachmentInfo {
+    pub name : String,
+    pub mime_type : Option<String>,
+    pub size : usize, // Decoded size in bytes
+    pub checksum : Option<String>,
+    pub creation_date : Option<String>,
+    pub page : Option<usize>, // Page of the attachment annotation, starting at 1
+    pub metadata : Option<Box<PdfStruct>>, // Metadata of an attached PDF
+}
+
+// Max nesting of PDFs attached to attached PDFs
+const MAX_ATTACHMENT_DEPTH : usize = 3;
+
+// Keys of the Info dictionary reported for each revision
+const INFO_KEYS : [&str; 8] = ["Title", "Author", "Subject", "Keywords", "Creator", "Producer", "CreationDate", "ModDate"];
+
 pub fn decode_bytes(bytes: &[u8]) -> String {
     let (cow, _, _) = WINDOWS_1252.decode(bytes); // Decode using Windows-1252
     let s = cow.to_string();
@@ -40,6 +83,15 @@ pub fn fetch_metadata(document : &Document, filepath : &str) -> PdfStruct{
         filename: String::new(),
         assumed_title: String::new(),
         author: Vec::new(),
+        page_count : document.get_pages().len(),
+        printed_pages : None,
+        revisions : Vec::new(),
+        xmp : None,
+        info : None,
+        link_doi : None,
+        notes : Vec::new(),
+        attachments : Vec::new(),
+        signatures : Vec::new(),
         api_metadata : None,
     };
     
@@ -66,28 +118,76 @@ pub fn extract_metadata(pdf : &mut document::Document, filepath : &str) -> PdfSt
     let mut meta_title = String::new();
     let mut meta_authors : Vec<String> = Vec::new();
 
-    // Get title from Info object
-    if let Some(title) = pdf.get_info("Title"){
+    // XMP metadata is preferred over the Info object
+    let xmp = pdf.get_xmp().filter(|x| !x.is_empty());
+    if let Some(title) = xmp.as_ref().and_then(|x| x.title.clone()){
         meta_title = title;
-    };
+    }
+
+    // Get title from Info object
+    let info = pdf.get_document_info();
+    if !is_accepted_title(&meta_title){
+        if let Some(title) = info.as_ref().and_then(|x| x.title.clone()){
+            meta_title = title;
+        };
+    }
 
     // If title is invalid, it is rejected
     if !is_accepted_title(&meta_title){
         meta_title = String::new();
     }
     
-    // Get authors
-    if let Some(authors) = pdf.get_info("Author"){
-        meta_authors = split_authors(&authors);
+    // Get authors, XMP lists each author separately
+    if let Some(creators) = xmp.as_ref().map(|x| &x.creators).filter(|x| !x.is_empty()){
+        meta_authors = creators.clone();
+    } else if let Some(authors) = info.as_ref().and_then(|x| x.author.as_ref()){
+        meta_authors = split_authors(authors);
     };
 
-    // Get title from text
-    let assumed_title = get_probable_title(pdf);
+    // Get title from text, books and theses often have a title in their first bookmark instead
+    let mut assumed_title = get_probable_title(pdf);
+    if assumed_title.is_empty(){
+        assumed_title = get_bookmark_title(pdf);
+    }
+
+    // DOI links in the header identify the article
+    let link_doi = get_link_doi(pdf);
+
+    // Digital signatures of signed theses and forms
+    let signatures : Vec<FormField> = pdf.get_form_fields().into_iter().filter(|x| x.signature.is_some()).collect();
 
     // Get filename
     let fname = split_name(filepath).unwrap_or(String::new());
+    let page_count = pdf.page_count();
+
+    // Printed page numbers, articles in a journal issue rarely start at page 1
+    let printed_pages = pdf.get_page_labels().and_then(|labels| Some((labels.first()?.clone(), labels.last()?.clone())));
     
-    PdfStruct{_path : filepath.to_string(), metadata_title : meta_title, assumed_title : assumed_title, author : meta_authors, api_metadata : None, filename : fname}
+    PdfStruct{_path : filepath.to_string(), metadata_title : meta_title, assumed_title : assumed_title, author : meta_authors, page_count, printed_pages, revisions : Vec::new(), xmp, info, link_doi, notes : Vec::new(), attachments : Vec::new(), signatures, api_metadata : None, filename : fname}
+}
+
+/// Reads the Info metadata of every revision of the PDF, oldest first
+pub fn extract_revisions(pdf : &mut document::Document) -> Vec<RevisionInfo>{
+    let mut revisions : Vec<RevisionInfo> = Vec::new();
+    let current = pdf.revision();
+    let changed : Vec<usize> = pdf.revisions().iter().map(|x| x.changed_ids.len()).collect();
+
+    for (revision, changed_objects) in changed.into_iter().enumerate(){
+        if pdf.set_revision(revision).is_err(){
+            continue;
+        }
+        let mut info : Vec<(String, String)> = Vec::new();
+        for key in INFO_KEYS{
+            if let Some(value) = pdf.get_info(key){
+                info.push((key.to_string(), value));
+            }
+        }
+        revisions.push(RevisionInfo{revision, changed_objects, info});
+    }
+
+    // Go back to the revision that was viewed
+    let _ = pdf.set_revision(current);
+    revisions
 }
 
 /// Collects the Title and Author from the PDF's trailer "Info" dictionary.
@@ -128,9 +228,94 @@ fn split_authors(input: &str) -> Vec<String> {
         .collect()
 }
 
+/// Reads the embedded files of the PDF, and the metadata of attached PDFs.
+/// If a directory is given, the files are saved there
+pub fn extract_attachments(pdf : &mut document::Document, extract_dir : Option<&str>, depth : usize) -> Vec<AttachmentInfo>{
+    let mut attachments : Vec<AttachmentInfo> = Vec::new();
+    for file in pdf.get_embedded_files(){
+        let data = match pdf.get_embedded_file_data(&file) {
+            Ok(x) => x,
+            Err(err) => {
+                println!("Failed to read attachment {}: {}", file.name, err);
+                continue;
+            }
+        };
+
+        // Save the file
+        if let Some(dir) = extract_dir {
+            if let Err(err) = save_attachment(dir, &file.name, &data){
+                println!("{}", err);
+            }
+        }
+
+        // Read the metadata of an attached PDF, it can have attachments too
+        let mut metadata : Option<Box<PdfStruct>> = None;
+        if file.is_pdf() && depth < MAX_ATTACHMENT_DEPTH {
+            if let Ok(mut attached_pdf) = document::Document::from_bytes(data.clone()){
+                let mut attached_meta = extract_metadata(&mut attached_pdf, &file.name);
+                attached_meta.attachments = extract_attachments(&mut attached_pdf, extract_dir, depth+1);
+                metadata = Some(Box::new(attached_meta));
+            }
+        }
+
+        attachments.push(AttachmentInfo {
+            name : file.name,
+            mime_type : file.mime_type,
+            size : data.len(),
+            checksum : file.checksum,
+            creation_date : file.creation_date.map(|x| x.to_string()),
+            page : file.page_index.map(|x| x+1),
+            metadata,
+        });
+    }
+    attachments
+}
+
+/// Returns a DOI from the link annotations on the first page.
+/// Links on later pages often point to cited works
+pub fn get_link_doi(pdf : &mut document::Document) -> Option<String>{
+    let first_page = pdf.get_page(0)?;
+    first_page.get_annotations(pdf)
+        .into_iter()
+        .filter_map(|annot| annot.uri)
+        .filter(|uri| uri.to_lowercase().contains("doi"))
+        .find_map(|uri| find_doi(&uri))
+}
+
+/// Finds a DOI (10.xxxx/suffix) in a text or url
+pub fn find_doi(text : &str) -> Option<String>{
+    let decoded = urlencoding::decode(text).map(|x| x.to_string()).unwrap_or(text.to_string());
+    let doi = DOI_REGEX.find(&decoded)?.as_str();
+    let doi = doi.trim_end_matches(['.', ',', ';', ')', ']']);
+    Some(doi.to_string())
+}
+
+/// Compares the printed page numbers of the PDF with a page range from the API, such as 231-245.
+/// None if either is missing
+pub fn pages_match(pdf : &PdfStruct, api_pages : &str) -> Option<bool>{
+    let (first, last) = pdf.printed_pages.as_ref()?;
+    let mut api_range = api_pages.split('-').map(|x| x.trim()).filter(|x| !x.is_empty());
+    let api_first = api_range.next()?;
+    let api_last = api_range.next().unwrap_or(api_first);
+    Some(first.eq_ignore_ascii_case(api_first) && last.eq_ignore_ascii_case(api_last))
+}
+
+/// Returns the first top level bookmark that can be accepted as a title
+pub fn get_bookmark_title(pdf : &mut document::Document) -> String{
+    pdf.get_outline()
+        .into_iter()
+        .filter(|item| item.level == 0)
+        .map(|item| item.title.trim().to_string())
+        .find(|title| is_accepted_title(title))
+        .unwrap_or(String::new())
+}
+
 /// Returns the most probable title from a list of Text elements
 pub fn get_probable_title(pdf : &mut document::Document) -> String{
-    let Some(mut texts) = pdf.get_text_from_page(0) else {
+    let Some(first_page) = pdf.get_page(0) else {
+        return String::new();
+    };
+    let Some(mut texts) = first_page.get_text(pdf) else {
         return String::new();
     };
     if texts.len() == 0{
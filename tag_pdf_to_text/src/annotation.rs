@@ -0,0 +1,167 @@
+use crate::document::Document;
+use crate::info::PdfDate;
+use crate::outline::get_link_target;
+use crate::page::get_rectangle;
+use crate::pdf_object::PdfVar;
+use crate::text_parser::TextRun;
+
+// Annotations that hold a comment, or mark text on the page
+const NOTE_SUBTYPES : [&str; 5] = ["Highlight", "Underline", "StrikeOut", "Text", "FreeText"];
+
+// Annotations that mark the text under their QuadPoints
+const MARKUP_SUBTYPES : [&str; 3] = ["Highlight", "Underline", "StrikeOut"];
+
+/// An annotation on a page, such as a link or a comment
+#[derive(Debug, Clone)]
+pub struct Annotation {
+    pub subtype : String, // Link, Text, Highlight, ...
+    pub rect : Option<[f64; 4]>, // Position on the page [llx lly urx ury]
+    pub contents : Option<String>,
+    pub author : Option<String>, // /T
+    pub modified : Option<PdfDate>, // /M
+    pub uri : Option<String>, // Target of a URI action
+    pub dest_page : Option<usize>, // Target page of a link, starting at 0
+    pub quad_points : Vec<[f64; 8]>, // Quadrilaterals of the text marked by a text markup annotation
+    pub(crate) object : PdfVar,
+}
+
+/// A comment or a text markup made by a reader of the document
+#[derive(Debug, Clone)]
+pub struct Note {
+    pub subtype : String,
+    pub page_index : usize,
+    pub contents : Option<String>,
+    pub author : Option<String>,
+    pub modified : Option<PdfDate>,
+    pub marked_text : Option<String>, // Page text under a highlight, underline or strikeout
+}
+
+impl Annotation {
+    /// Reads an annotation dictionary
+    pub(crate) fn from(doc : &mut Document, object : PdfVar) -> Option<Self>{
+        let subtype = object.get_dict_value("Subtype")?.get_name()?;
+        let rect = object.get_dict_value("Rect")
+            .and_then(|x| doc.resolve(x))
+            .and_then(|x| get_rectangle(&x));
+        let contents = object.get_dict_str("Contents", doc);
+        let author = object.get_dict_str("T", doc);
+        let modified = object.get_dict_str("M", doc).and_then(|x| PdfDate::parse(&x));
+
+        // QuadPoints, x1 y1 x2 y2 x3 y3 x4 y4 for each marked area
+        let mut quad_points : Vec<[f64; 8]> = Vec::new();
+        if let Some(PdfVar::Array(points)) = object.get_dict_value("QuadPoints").and_then(|x| doc.resolve(x)){
+            let values : Vec<f64> = points.iter().filter_map(|x| x.get_f64()).collect();
+            for quad in values.chunks_exact(8){
+                let mut points : [f64; 8] = [0.0; 8];
+                points.copy_from_slice(quad);
+                quad_points.push(points);
+            }
+        }
+
+        // URI action, << /S /URI /URI (http://...) >>
+        let mut uri : Option<String> = None;
+        if let Some(action) = object.get_dict_value("A").and_then(|x| doc.resolve(x)){
+            if action.get_dict_value("S").and_then(|x| x.get_name()).as_deref() == Some("URI"){
+                uri = action.get_dict_str("URI", doc);
+            }
+        }
+        let dest_page = get_link_target(doc, &object);
+
+        Some(Annotation { subtype, rect, contents, author, modified, uri, dest_page, quad_points, object })
+    }
+
+    /// Returns the value of a key in the annotation dictionary
+    pub(crate) fn get_dict_value(&self, key : &str) -> Option<&PdfVar>{
+        self.object.get_dict_value(key)
+    }
+}
+
+/// Reads the annotations in a pages /Annots array
+pub(crate) fn get_annotations(doc : &mut Document, annots_obj : &PdfVar) -> Vec<Annotation>{
+    let mut annotations : Vec<Annotation> = Vec::new();
+    let Some(PdfVar::Array(annots)) = doc.resolve(annots_obj) else{
+        return annotations;
+    };
+    for annot_ref in annots.iter(){
+        let Some(annot_obj) = doc.resolve(annot_ref) else{
+            continue;
+        };
+        if let Some(annotation) = Annotation::from(doc, annot_obj){
+            annotations.push(annotation);
+        }
+    }
+    annotations
+}
+
+/// Returns the comments and text markups among the annotations of a page
+pub(crate) fn get_notes(annotations : Vec<Annotation>, page_index : usize, runs : &Vec<TextRun>) -> Vec<Note>{
+    let mut notes : Vec<Note> = Vec::new();
+    for annotation in annotations{
+        if !NOTE_SUBTYPES.contains(&annotation.subtype.as_str()){
+            continue;
+        }
+        let mut marked_text : Option<String> = None;
+        if MARKUP_SUBTYPES.contains(&annotation.subtype.as_str()){
+            marked_text = get_marked_text(runs, &annotation.quad_points);
+        }
+        notes.push(Note {
+            subtype : annotation.subtype,
+            page_index,
+            contents : annotation.contents,
+            author : annotation.author,
+            modified : annotation.modified,
+            marked_text,
+        });
+    }
+    notes
+}
+
+/// Returns true if any of the annotations marks text on the page
+pub(crate) fn has_text_markup(annotations : &Vec<Annotation>) -> bool{
+    annotations.iter().any(|x| MARKUP_SUBTYPES.contains(&x.subtype.as_str()))
+}
+
+/// Returns the page text under the quadrilaterals of a text markup annotation
+fn get_marked_text(runs : &Vec<TextRun>, quad_points : &Vec<[f64; 8]>) -> Option<String>{
+    let mut marked = String::new();
+    for quad in quad_points{
+        let xs = [quad[0], quad[2], quad[4], quad[6]];
+        let ys = [quad[1], quad[3], quad[5], quad[7]];
+        let min_x = xs.iter().copied().fold(f64::INFINITY, f64::min);
+        let max_x = xs.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let min_y = ys.iter().copied().fold(f64::INFINITY, f64::min);
+        let max_y = ys.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+
+        // x where the last marked char ended, a gap after it is a space
+        let mut last_end : Option<f64> = None;
+        for run in runs{
+            // The middle of the glyphs has to be inside the quad, quads cover the descenders too
+            let font_size = run.font_size.abs();
+            let mid_y = run.y + 0.35*font_size;
+            if mid_y < min_y || mid_y > max_y{
+                continue;
+            }
+            let mut x = run.x;
+            for (chr, advance) in run.chars.chars().zip(run.advances.iter()){
+                let center = x + advance/2.0;
+                if center >= min_x && center <= max_x{
+                    if let Some(end) = last_end{
+                        if x - end > 0.15*font_size{
+                            marked.push(' ');
+                        }
+                    }
+                    marked.push(chr);
+                    last_end = Some(x + advance);
+                }
+                x += advance;
+            }
+        }
+        marked.push(' ');
+    }
+
+    let marked = marked.split_whitespace().collect::<Vec<&str>>().join(" ");
+    if marked.is_empty(){
+        return None;
+    }
+    Some(marked)
+}
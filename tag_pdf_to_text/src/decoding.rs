@@ -1,7 +1,7 @@
 use std::char;
 use std::io::Read;
 
-use crate::document::{Document, PdfError};
+use crate::document::{Document, ErrorKind, PdfError};
 use crate::encoding::PDFDOC_MAP;
 use crate::pdf_object::PdfVar;
 use flate2::read::ZlibDecoder;
@@ -59,10 +59,18 @@ pub(crate) fn decode_pdfdoc(bytes : &Vec<u32>) -> String{
     return decoded;
 }
 
-/// PNG-decoding, returns decoded stream
-pub(crate) fn png_decode(stream: &Vec<u8>, _predictor: usize, columns: usize) -> Result<Vec<u8>, PdfError>{
+/// PNG-decoding, returns decoded stream. Each row starts with the filter type of the row
+pub(crate) fn png_decode(stream: &Vec<u8>, colors: usize, bits_per_component: usize, columns: usize) -> Result<Vec<u8>, PdfError>{
+    // Bytes per pixel, at least 1, and bytes per row
+    let bpp = (colors * bits_per_component).div_ceil(8);
+    let row_len = (colors * bits_per_component * columns).div_ceil(8);
+
     let mut i : usize = 0;
     let mut decoded : Vec<u8> = Vec::new();
+    if row_len >= stream.len() {
+        return Ok(decoded);
+    }
+    let mut prev_row : Vec<u8> = vec![0; row_len];
 
     while i < stream.len() {
         // Get filter type
@@ -70,53 +78,355 @@ pub(crate) fn png_decode(stream: &Vec<u8>, _predictor: usize, columns: usize) ->
         i += 1;
 
         // Break if data ends
-        if i + columns > stream.len() {
+        if i + row_len > stream.len() {
             break;
         }
 
-        match filter_type { 
-            2 => {
-                let dec_len = decoded.len() as i32; 
-                for j in 0..columns {
-                    let top_index : i32 = dec_len-(columns as i32)+(j as i32);
-                    if top_index >= 0{
-                        decoded.push(stream[j+i].wrapping_add(decoded[top_index as usize]));
-                    }
-                    else{
-                        decoded.push(stream[j+i]);
-                    }
+        let mut row : Vec<u8> = stream[i..i+row_len].to_vec();
+        for j in 0..row_len {
+            // Left, up and upper left bytes, 0 outside the image
+            let left = if j >= bpp { row[j-bpp] } else { 0 };
+            let up = prev_row[j];
+            let up_left = if j >= bpp { prev_row[j-bpp] } else { 0 };
+
+            let prediction = match filter_type {
+                0 => 0,
+                1 => left,
+                2 => up,
+                3 => ((left as u16 + up as u16) / 2) as u8,
+                4 => paeth(left, up, up_left),
+                _ => {
+                    // Unsupported filter type
+                    return Err(PdfError::new(ErrorKind::DecodeError, "decode png predictor"));
+                }
+            };
+            row[j] = row[j].wrapping_add(prediction);
+        }
+
+        decoded.extend_from_slice(&row);
+        prev_row = row;
+        i += row_len;
+    }
+
+    return Ok(decoded);
+}
+
+/// Paeth predictor, the neighbour closest to left + up - up_left
+fn paeth(left : u8, up : u8, up_left : u8) -> u8{
+    let estimate = left as i16 + up as i16 - up_left as i16;
+    let dist_left = (estimate - left as i16).abs();
+    let dist_up = (estimate - up as i16).abs();
+    let dist_up_left = (estimate - up_left as i16).abs();
+    if dist_left <= dist_up && dist_left <= dist_up_left {
+        return left;
+    }
+    if dist_up <= dist_up_left {
+        return up;
+    }
+    return up_left;
+}
+
+/// TIFF predictor 2, each component is the difference to the same component of the pixel to the left
+pub(crate) fn tiff_decode(stream: &Vec<u8>, colors: usize, bits_per_component: usize, columns: usize) -> Result<Vec<u8>, PdfError>{
+    let row_len = (colors * bits_per_component * columns).div_ceil(8);
+    let mut decoded : Vec<u8> = Vec::new();
+
+    for row in stream.chunks(row_len) {
+        let mut row = row.to_vec();
+        match bits_per_component {
+            8 => {
+                for j in colors..row.len() {
+                    row[j] = row[j].wrapping_add(row[j-colors]);
+                }
+            }
+            16 => {
+                for j in (colors*2..row.len()-1).step_by(2) {
+                    let left = u16::from_be_bytes([row[j-colors*2], row[j-colors*2+1]]);
+                    let value = u16::from_be_bytes([row[j], row[j+1]]).wrapping_add(left);
+                    row[j..j+2].copy_from_slice(&value.to_be_bytes());
                 }
             }
             _ => {
-                // Unsupported filter type
-                return Err(PdfError::DecodeError);
+                // Components of 1, 2 or 4 bits
+                let mask = (1u16 << bits_per_component) - 1;
+                let samples = row.len() * 8 / bits_per_component;
+                for k in colors..samples.min(colors*columns) {
+                    let left = get_sample(&row, k-colors, bits_per_component);
+                    let value = (get_sample(&row, k, bits_per_component) as u16 + left as u16) & mask;
+                    set_sample(&mut row, k, bits_per_component, value as u8);
+                }
             }
         }
-
-        i += columns;
+        decoded.extend_from_slice(&row);
     }
-
     return Ok(decoded);
 }
 
+/// Returns a component of less than 8 bits, the first component is in the high bits
+fn get_sample(row : &[u8], index : usize, bits : usize) -> u8{
+    let bit_pos = index * bits;
+    let shift = 8 - bits - bit_pos % 8;
+    (row[bit_pos / 8] >> shift) & ((1u16 << bits) - 1) as u8
+}
+
+/// Sets a component of less than 8 bits
+fn set_sample(row : &mut [u8], index : usize, bits : usize, value : u8){
+    let bit_pos = index * bits;
+    let shift = 8 - bits - bit_pos % 8;
+    let mask = (((1u16 << bits) - 1) as u8) << shift;
+    row[bit_pos / 8] = (row[bit_pos / 8] & !mask) | (value << shift);
+}
+
 /// Decode flate stream in place (functionally)
-pub(crate) fn decode_flate(data : &mut Vec<u8>) -> Result<(), PdfError>{
+pub(crate) fn decode_flate(data : &mut Vec<u8>, max_size : usize) -> Result<(), PdfError>{
     let data_copy = data.clone();
     data.clear();
-    let mut decoder = ZlibDecoder::new(data_copy.as_slice());
+
+    // One byte more than allowed is read, to know if the limit is exceeded
+    let mut decoder = ZlibDecoder::new(data_copy.as_slice()).take((max_size as u64).saturating_add(1));
     
     // Attempt to decode flate
-    match decoder.read_to_end(data){
-        Ok(_) => Ok(()),
-        Err(e) => {
-            Err(PdfError::DecodeError)
+    if let Err(e) = decoder.read_to_end(data){
+        return Err(PdfError::new(ErrorKind::DecodeError, "decode flate stream").caused_by(e));
+    }
+    if data.len() > max_size{
+        return Err(PdfError::new(ErrorKind::LimitError, "decode flate stream"));
+    }
+    Ok(())
+}
+
+/// Decode ASCII hex stream in place, whitespace is ignored and > ends the data
+pub(crate) fn decode_ascii_hex(data : &mut Vec<u8>) -> Result<(), PdfError>{
+    let encoded = std::mem::take(data);
+    let mut high_digit : Option<u8> = None;
+    for byte in encoded{
+        if byte.is_ascii_whitespace() || byte == 0{
+            continue;
+        }
+        if byte == b'>'{
+            break;
+        }
+        let Some(digit) = (byte as char).to_digit(16) else{
+            return Err(PdfError::new(ErrorKind::DecodeError, "decode ascii hex stream"));
+        };
+        match high_digit.take() {
+            Some(high) => data.push(high*16 + digit as u8),
+            None => high_digit = Some(digit as u8),
+        }
+    }
+
+    // A missing last digit is 0
+    if let Some(high) = high_digit{
+        data.push(high*16);
+    }
+    Ok(())
+}
+
+/// Decode ASCII base-85 stream in place, whitespace is ignored and ~> ends the data
+pub(crate) fn decode_ascii85(data : &mut Vec<u8>) -> Result<(), PdfError>{
+    let encoded = std::mem::take(data);
+    let encoded = encoded.strip_prefix(b"<~").unwrap_or(&encoded);
+    let mut group : Vec<u32> = Vec::new();
+    for &byte in encoded{
+        if byte.is_ascii_whitespace() || byte == 0{
+            continue;
+        }
+        if byte == b'~'{
+            break;
+        }
+
+        // z is four zero bytes, only allowed between groups
+        if byte == b'z' && group.is_empty(){
+            data.extend_from_slice(&[0, 0, 0, 0]);
+            continue;
+        }
+        if !(b'!'..=b'u').contains(&byte){
+            return Err(PdfError::new(ErrorKind::DecodeError, "decode ascii85 stream"));
         }
+        group.push((byte - b'!') as u32);
+        if group.len() == 5{
+            data.extend_from_slice(&decode_ascii85_group(&group)?);
+            group.clear();
+        }
+    }
+
+    // The last group can have 2 to 4 chars, it is padded with u and gives one byte less than its length
+    if group.len() == 1{
+        return Err(PdfError::new(ErrorKind::DecodeError, "decode ascii85 stream"));
+    }
+    if !group.is_empty(){
+        let length = group.len();
+        group.resize(5, 84);
+        data.extend_from_slice(&decode_ascii85_group(&group)?[..length-1]);
+    }
+    Ok(())
+}
+
+/// Converts 5 base-85 digits to 4 bytes
+fn decode_ascii85_group(group : &[u32]) -> Result<[u8; 4], PdfError>{
+    let mut value : u64 = 0;
+    for digit in group{
+        value = value*85 + *digit as u64;
+    }
+    if value > u32::MAX as u64{
+        return Err(PdfError::new(ErrorKind::DecodeError, "decode ascii85 stream"));
     }
+    Ok((value as u32).to_be_bytes())
+}
+
+/// Decode run length stream in place
+pub(crate) fn decode_run_length(data : &mut Vec<u8>, max_size : usize) -> Result<(), PdfError>{
+    let encoded = std::mem::take(data);
+    let mut i : usize = 0;
+    while i < encoded.len(){
+        let length = encoded[i] as usize;
+        i += 1;
+        match length {
+            // Copy the next length+1 bytes
+            0..=127 => {
+                let Some(run) = encoded.get(i..i+length+1) else{
+                    return Err(PdfError::new(ErrorKind::DecodeError, "decode run length stream"));
+                };
+                data.extend_from_slice(run);
+                i += length+1;
+            }
+            // End of data
+            128 => break,
+            // Repeat the next byte 257-length times
+            _ => {
+                let Some(byte) = encoded.get(i) else{
+                    return Err(PdfError::new(ErrorKind::DecodeError, "decode run length stream"));
+                };
+                data.extend(std::iter::repeat_n(*byte, 257-length));
+                i += 1;
+            }
+        }
+        if data.len() > max_size{
+            return Err(PdfError::new(ErrorKind::LimitError, "decode run length stream"));
+        }
+    }
+    Ok(())
+}
+
+/// Decode LZW stream in place. With early change, the code length grows one code earlier
+pub(crate) fn decode_lzw(data : &mut Vec<u8>, early_change : bool, max_size : usize) -> Result<(), PdfError>{
+    let encoded = std::mem::take(data);
+
+    // Codes 0-255 are single bytes, 256 clears the table and 257 ends the data
+    let mut table : Vec<Vec<u8>> = (0..=255u8).map(|x| vec![x]).collect();
+    table.push(Vec::new());
+    table.push(Vec::new());
+
+    let mut code_length : usize = 9;
+    let mut bit_buffer : u32 = 0;
+    let mut bit_count : usize = 0;
+    let mut prev_code : Option<usize> = None;
+
+    for byte in encoded{
+        bit_buffer = (bit_buffer << 8) | byte as u32;
+        bit_count += 8;
+
+        while bit_count >= code_length{
+            // Codes are read with the most significant bit first
+            bit_count -= code_length;
+            let code = (bit_buffer >> bit_count) as usize & ((1 << code_length) - 1);
+            bit_buffer &= (1 << bit_count) - 1;
+
+            if code == 256{
+                table.truncate(258);
+                code_length = 9;
+                prev_code = None;
+                continue;
+            }
+            if code == 257{
+                return Ok(());
+            }
+
+            // A code that is not in the table yet is the previous entry plus its own first byte
+            let entry = match prev_code {
+                _ if code < table.len() => table[code].clone(),
+                Some(prev) if code == table.len() => {
+                    let mut entry = table[prev].clone();
+                    entry.push(table[prev][0]);
+                    entry
+                }
+                _ => {
+                    return Err(PdfError::new(ErrorKind::DecodeError, "decode lzw stream"));
+                }
+            };
+            data.extend_from_slice(&entry);
+            if data.len() > max_size{
+                return Err(PdfError::new(ErrorKind::LimitError, "decode lzw stream"));
+            }
+
+            // Add the previous entry plus the first byte of this one
+            if let Some(prev) = prev_code{
+                if table.len() < 4096{
+                    let mut new_entry = table[prev].clone();
+                    new_entry.push(entry[0]);
+                    table.push(new_entry);
+                }
+            }
+            prev_code = Some(code);
+
+            // Codes are 9 to 12 bits long
+            if table.len() + early_change as usize >= (1 << code_length) && code_length < 12{
+                code_length += 1;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Decodes the data of one stream filter, with the decode parameters of that filter
+pub(crate) fn apply_filter(data : Vec<u8>, filter : &str, decodeparms_obj : Option<&PdfVar>, doc : &mut Document) -> Result<Vec<u8>, PdfError>{
+    let max_size = doc.limits.max_stream_size;
+
+    let mut decoded = data;
+    match filter {
+        "FlateDecode" => {
+            decode_flate(&mut decoded, max_size)?;
+        }
+        "LZWDecode" => {
+            // EarlyChange is 1 unless the parameters say otherwise
+            let early_change = decodeparms_obj
+                .and_then(|x| x.get_dict_value("EarlyChange"))
+                .and_then(|x| x.get_usize(doc)) != Some(0);
+            decode_lzw(&mut decoded, early_change, max_size)?;
+        }
+        "ASCIIHexDecode" => {
+            decode_ascii_hex(&mut decoded)?;
+        }
+        "ASCII85Decode" => {
+            decode_ascii85(&mut decoded)?;
+        }
+        "RunLengthDecode" => {
+            decode_run_length(&mut decoded, max_size)?;
+        }
+        _ => {
+            // Unknown filter type
+            return Err(PdfError::new(ErrorKind::DecodeError, "decode stream"));
+        }
+    }
+
+    if decoded.len() > max_size{
+        return Err(PdfError::new(ErrorKind::LimitError, "decode stream"));
+    }
+
+    // Predictors are only used by Flate and LZW
+    if let Some(decodeparms_obj) = decodeparms_obj{
+        if matches!(filter, "FlateDecode" | "LZWDecode"){
+            return handle_decodeparms(decoded, decodeparms_obj, doc);
+        }
+    }
+    return Ok(decoded);
 }
 
 /// Processes a decoded stream based on the decodeparms
 pub(crate) fn handle_decodeparms(stream : Vec<u8>, decodeparms_obj : &PdfVar, doc : &mut Document) -> Result<Vec<u8>, PdfError>{
     let mut predictor : usize = 1;
+    let mut colors : usize = 1;
+    let mut bits_per_component : usize = 8;
     let mut columns : usize = 1;
 
     // Fetch Predictor value
@@ -125,6 +435,20 @@ pub(crate) fn handle_decodeparms(stream : Vec<u8>, decodeparms_obj : &PdfVar, do
             predictor = pred_usize;
         };
     };
+
+    // Fetch Colors value, components per pixel
+    if let Some(colors_obj) = decodeparms_obj.get_dict_value("Colors"){
+        if let Some(colors_usize) = colors_obj.get_usize(doc) {
+            colors = colors_usize;
+        };
+    };
+
+    // Fetch BitsPerComponent value
+    if let Some(bpc_obj) = decodeparms_obj.get_dict_value("BitsPerComponent"){
+        if let Some(bpc_usize) = bpc_obj.get_usize(doc) {
+            bits_per_component = bpc_usize;
+        };
+    };
     
     // Fetch Columns value
     if let Some(columns_obj) = decodeparms_obj.get_dict_value("Columns"){
@@ -133,11 +457,26 @@ pub(crate) fn handle_decodeparms(stream : Vec<u8>, decodeparms_obj : &PdfVar, do
         };
     };
 
+    // No prediction
+    if predictor == 1{
+        return Ok(stream);
+    }
+
+    let row_bits = colors.checked_mul(bits_per_component).and_then(|x| x.checked_mul(columns));
+    if colors == 0 || columns == 0 || row_bits.is_none() || ![1, 2, 4, 8, 16].contains(&bits_per_component){
+        return Err(PdfError::new(ErrorKind::DecodeError, "apply decode parameters"));
+    }
+
+    // Handle TIFF-decoding
+    if predictor == 2{
+        return tiff_decode(&stream, colors, bits_per_component, columns);
+    }
+
     // Handle PNG-decoding
     if (10..16).contains(&predictor){
-        return png_decode(&stream, predictor, columns);
+        return png_decode(&stream, colors, bits_per_component, columns);
     }
-    return Err(PdfError::DecodeError);
+    return Err(PdfError::new(ErrorKind::DecodeError, "apply decode parameters"));
 }
 
 
@@ -149,3 +488,175 @@ pub(crate) fn get_256_repr(bytes : &[u8]) -> usize{
     }
     return output;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// PNG encodes rows, each row with its own filter type
+    fn png_encode(raw : &[u8], bpp : usize, row_len : usize, filter_types : &[u8]) -> Vec<u8>{
+        let mut encoded : Vec<u8> = Vec::new();
+        let mut prev_row : Vec<u8> = vec![0; row_len];
+        for (row, filter_type) in raw.chunks(row_len).zip(filter_types){
+            encoded.push(*filter_type);
+            for j in 0..row_len{
+                let left = if j >= bpp { row[j-bpp] } else { 0 };
+                let up = prev_row[j];
+                let up_left = if j >= bpp { prev_row[j-bpp] } else { 0 };
+                let prediction = match filter_type {
+                    0 => 0,
+                    1 => left,
+                    2 => up,
+                    3 => ((left as u16 + up as u16) / 2) as u8,
+                    _ => paeth(left, up, up_left),
+                };
+                encoded.push(row[j].wrapping_sub(prediction));
+            }
+            prev_row = row.to_vec();
+        }
+        encoded
+    }
+
+    /// LZW encodes data, the code length follows the table size of the decoder
+    fn lzw_encode(data : &[u8], early_change : bool) -> Vec<u8>{
+        let mut table : HashMap<Vec<u8>, usize> = (0..=255u8).map(|x| (vec![x], x as usize)).collect();
+        let mut codes : Vec<(usize, usize)> = vec![(256, 9)];
+        let mut code_length = 9;
+        let mut emitted = 0;
+        let mut emit = |code : usize, codes : &mut Vec<(usize, usize)>, code_length : &mut usize|{
+            codes.push((code, *code_length));
+            // The decoder adds an entry for every code after the first
+            let decoder_len = 258 + emitted;
+            emitted += 1;
+            if decoder_len + early_change as usize >= (1 << *code_length) && *code_length < 12{
+                *code_length += 1;
+            }
+        };
+
+        let mut word : Vec<u8> = Vec::new();
+        for byte in data{
+            let mut next = word.clone();
+            next.push(*byte);
+            if table.contains_key(&next){
+                word = next;
+                continue;
+            }
+            emit(table[&word], &mut codes, &mut code_length);
+            let new_code = table.len() + 2;
+            table.insert(next, new_code);
+            word = vec![*byte];
+        }
+        emit(table[&word], &mut codes, &mut code_length);
+        codes.push((257, code_length));
+
+        // Codes are written with the most significant bit first
+        let mut encoded : Vec<u8> = Vec::new();
+        let mut bit_buffer : u32 = 0;
+        let mut bit_count = 0;
+        for (code, length) in codes{
+            bit_buffer = (bit_buffer << length) | code as u32;
+            bit_count += length;
+            while bit_count >= 8{
+                bit_count -= 8;
+                encoded.push((bit_buffer >> bit_count) as u8);
+                bit_buffer &= (1 << bit_count) - 1;
+            }
+        }
+        if bit_count > 0{
+            encoded.push((bit_buffer << (8 - bit_count)) as u8);
+        }
+        encoded
+    }
+
+    /// Pseudo random bytes from a small alphabet, so that strings repeat
+    fn sample_data(size : usize) -> Vec<u8>{
+        let mut state : u32 = 12345;
+        (0..size).map(|_| {
+            state = state.wrapping_mul(1103515245).wrapping_add(12345);
+            b"abcdefgh"[(state >> 16) as usize % 8]
+        }).collect()
+    }
+
+    #[test]
+    fn png_all_filter_types(){
+        // 3 colors of 8 bits, 4 pixels per row, one row per filter type
+        let raw : Vec<u8> = (0..5*12).map(|x : u8| x.wrapping_mul(37).wrapping_add(200)).collect();
+        let encoded = png_encode(&raw, 3, 12, &[0, 1, 2, 3, 4]);
+        assert_eq!(png_decode(&encoded, 3, 8, 4).unwrap(), raw);
+    }
+
+    #[test]
+    fn png_16_bit_components(){
+        // 2 colors of 16 bits, 4 bytes per pixel
+        let raw : Vec<u8> = (0..5*16).map(|x : u8| x.wrapping_mul(91)).collect();
+        let encoded = png_encode(&raw, 4, 16, &[4, 3, 2, 1, 0]);
+        assert_eq!(png_decode(&encoded, 2, 16, 4).unwrap(), raw);
+    }
+
+    #[test]
+    fn png_unknown_filter_type(){
+        let encoded = vec![5, 1, 2, 3, 4];
+        assert!(png_decode(&encoded, 1, 8, 4).is_err());
+    }
+
+    #[test]
+    fn tiff_8_bit_components(){
+        // 2 colors, 2 pixels per row, each row starts from 0
+        let encoded = vec![10, 20, 1, 2, 3, 4, 5, 250];
+        assert_eq!(tiff_decode(&encoded, 2, 8, 2).unwrap(), vec![10, 20, 11, 22, 3, 4, 8, 254]);
+        let encoded = vec![100, 0, 0, 7, 1, 255];
+        assert_eq!(tiff_decode(&encoded, 1, 8, 3).unwrap(), vec![100, 100, 100, 7, 8, 7]);
+    }
+
+    #[test]
+    fn tiff_16_bit_components(){
+        let encoded = vec![0x01, 0x00, 0x00, 0xFF, 0xFF, 0x01];
+        assert_eq!(tiff_decode(&encoded, 1, 16, 3).unwrap(), vec![0x01, 0x00, 0x01, 0xFF, 0x01, 0x00]);
+    }
+
+    #[test]
+    fn tiff_4_bit_components(){
+        // Samples 1 1 1 15, the sums wrap at 16
+        let encoded = vec![0x11, 0x1F];
+        assert_eq!(tiff_decode(&encoded, 1, 4, 4).unwrap(), vec![0x12, 0x32]);
+    }
+
+    #[test]
+    fn lzw_early_change_example(){
+        // The example of the LZWDecode filter in the pdf specification
+        let mut data = vec![0x80, 0x0B, 0x60, 0x50, 0x22, 0x0C, 0x0C, 0x85, 0x01];
+        decode_lzw(&mut data, true, 1000).unwrap();
+        assert_eq!(data, vec![45, 45, 45, 45, 45, 65, 45, 45, 45, 66]);
+    }
+
+    #[test]
+    fn lzw_early_change_1(){
+        // Long enough for the code length to grow to 12 bits
+        let raw = sample_data(8000);
+        let mut data = lzw_encode(&raw, true);
+        decode_lzw(&mut data, true, raw.len()).unwrap();
+        assert_eq!(data, raw);
+    }
+
+    #[test]
+    fn lzw_early_change_0(){
+        let raw = sample_data(8000);
+        let mut data = lzw_encode(&raw, false);
+        decode_lzw(&mut data, false, raw.len()).unwrap();
+        assert_eq!(data, raw);
+
+        // Read with the wrong code length switch the data is garbled
+        let mut data = lzw_encode(&raw, false);
+        let decoded = decode_lzw(&mut data, true, raw.len());
+        assert!(decoded.is_err() || data != raw);
+    }
+
+    #[test]
+    fn lzw_size_limit(){
+        let raw = sample_data(1000);
+        let mut data = lzw_encode(&raw, true);
+        let error = decode_lzw(&mut data, true, 999).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::LimitError);
+    }
+}
@@ -1,23 +1,42 @@
-use std::{fs::{self}, io};
+use std::{collections::{HashMap, HashSet}, error::Error, fmt, fs::{self}, io::{self, Read}};
 
-use crate::text_parser::{get_page_resources, read_objects_text, Text};
-use crate::pdf_object::{cmp_u8, parse_object, PdfVar};
+use crate::annotation::Note;
+use crate::page::{Page, Pages};
+use crate::text_parser::Text;
+use crate::pdf_object::{cmp_u8, is_delimiter, parse_object, PdfVar};
 use crate::decoding::get_256_repr;
+use crate::security::SecurityHandler;
+use crate::xmp::XmpMetadata;
+use crate::info::DocumentInfo;
+use crate::outline::{get_outline, OutlineItem};
+use crate::embedded::{get_embedded_file_data, get_embedded_files, EmbeddedFile};
+use crate::tree::NameTree;
+use crate::page_labels::get_page_labels;
+use crate::form::{get_form_fields, FormField};
+
+#[derive(Debug, Clone)]
+pub struct Trailer{
+    pub info : usize, // optional, documents information dictionary
+    pub root : usize, // Catalog directory
+    pub size : usize, // total number of entries in the files xref-tables
+    pub encrypt : usize,
+    pub id : Vec<u8>, // First part of the file identifier
+    // Prev is not stored
+}
 
-#[derive(Debug)]
-struct Trailer{
-    info : usize, // optional, documents information dictionary
-    root : usize, // Catalog directory
-    size : usize, // total number of entries in the files xref-tables
-    encrypt : usize,
-    // Prev, id are not stored
+/// A saved version of the document, each incremental update adds a revision
+#[derive(Debug, Clone)]
+pub struct Revision{
+    pub xref_offset : usize, // Offset of the xref table or stream of this revision
+    pub trailer : Trailer,
+    pub changed_ids : Vec<usize>, // Objects added, changed or freed in this revision
 }
 
 #[derive(Debug)]
 struct ObjectRef {
     compressed : u8,
     xref : usize,
-    _version : usize,
+    generation : usize, // Index in the ObjStm for compressed objects
 }
 
 #[derive(Debug)]
@@ -25,15 +44,49 @@ pub struct Document {
     xref : Vec<ObjectRef>,
     trailer : Trailer,
     pub(crate) data : Vec<u8>,
+    file_size : usize, // length of the file, objects unpacked while reading are appended to data after it
     pub(crate) it : usize,
+    recovered : bool, // true if the xref table was rebuilt by scanning the document
+    object_cache : HashMap<usize, PdfVar>, // parsed objects, by object id
+    page_ids : Option<Vec<usize>>, // object ids of all pages, built on first page lookup
+    security : Option<SecurityHandler>, // set if the document is encrypted with a supported handler
+    revisions : Vec<Revision>, // oldest first, empty if the xref table was rebuilt
+    revision : usize, // the revision the document is viewed as
+    warnings : Vec<PdfError>, // problems that were worked around or skipped when reading the document
+    pub(crate) limits : Limits,
+    pub(crate) nesting : usize, // depth of the arrays and dictionaries being parsed
+    loading : Vec<usize>, // ids of the objects being read, an object that needs itself to be read is a cycle
 }
 
-#[derive(Debug)]
-pub enum PdfError {
+/// Limits on the resources used to read a document, to read untrusted files safely
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    pub max_stream_size : usize, // Max decoded size of one stream, in bytes
+    pub max_total_decoded : usize, // Max bytes of decoded data kept in memory, unpacked objects, decrypted streams and page content
+    pub max_objects : usize, // Max number of entries in the xref table
+    pub max_depth : usize, // Max nesting of arrays, dictionaries, page trees and object stream chains
+}
+
+impl Default for Limits {
+    fn default() -> Self{
+        Limits {
+            max_stream_size : 128 * 1024 * 1024,
+            max_total_decoded : 1024 * 1024 * 1024,
+            max_objects : 5_000_000,
+            max_depth : 128,
+        }
+    }
+}
+
+/// The kind of error that occurred when reading a document
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ErrorKind {
+    CycleError,
     DecodeError,
     DictionaryError,
     DocumentError,
     HexError,
+    LimitError,
     LoadError,
     ObjectError,
     ObjectRefError,
@@ -43,77 +96,247 @@ pub enum PdfError {
     XrefError,
 }
 
+/// Error when reading a document, with where and why it failed
+#[derive(Debug)]
+pub struct PdfError {
+    pub kind : ErrorKind,
+    pub offset : Option<usize>, // Byte offset in the document
+    pub object_id : Option<usize>, // Object being read
+    pub operation : &'static str, // What was being done, e.g. "parse xref table"
+    source : Option<Box<dyn Error + Send + Sync>>, // Underlying cause
+}
+
+impl PdfError {
+    pub(crate) fn new(kind : ErrorKind, operation : &'static str) -> Self{
+        PdfError { kind, offset : None, object_id : None, operation, source : None }
+    }
+
+    /// Sets the byte offset where the error occurred
+    pub(crate) fn at(mut self, offset : usize) -> Self{
+        self.offset = Some(offset);
+        self
+    }
+
+    /// Sets the object that was being read, if not already set
+    pub(crate) fn in_object(mut self, object_id : usize) -> Self{
+        if self.object_id.is_none(){
+            self.object_id = Some(object_id);
+        }
+        self
+    }
+
+    /// Sets the underlying cause
+    pub(crate) fn caused_by(mut self, source : impl Into<Box<dyn Error + Send + Sync>>) -> Self{
+        self.source = Some(source.into());
+        self
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f : &mut fmt::Formatter<'_>) -> fmt::Result{
+        let description = match self {
+            ErrorKind::CycleError => "reference cycle",
+            ErrorKind::DecodeError => "failed to decode stream",
+            ErrorKind::DictionaryError => "invalid dictionary",
+            ErrorKind::DocumentError => "invalid document structure",
+            ErrorKind::HexError => "invalid hex string",
+            ErrorKind::LimitError => "resource limit exceeded",
+            ErrorKind::LoadError => "failed to load document",
+            ErrorKind::ObjectError => "invalid object",
+            ErrorKind::ObjectRefError => "invalid object reference",
+            ErrorKind::PdfHeaderError => "invalid pdf header",
+            ErrorKind::StreamError => "invalid stream",
+            ErrorKind::UnmatchedChar => "unexpected character",
+            ErrorKind::XrefError => "invalid cross-reference data",
+        };
+        write!(f, "{}", description)
+    }
+}
+
+impl fmt::Display for PdfError {
+    fn fmt(&self, f : &mut fmt::Formatter<'_>) -> fmt::Result{
+        write!(f, "{}: {}", self.operation, self.kind)?;
+        if let Some(offset) = self.offset{
+            write!(f, " at byte {}", offset)?;
+        }
+        if let Some(object_id) = self.object_id{
+            write!(f, " in object {}", object_id)?;
+        }
+        if let Some(source) = &self.source{
+            write!(f, " ({})", source)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for PdfError {
+    fn source(&self) -> Option<&(dyn Error + 'static)>{
+        match &self.source {
+            Some(source) => Some(source.as_ref()),
+            None => None,
+        }
+    }
+}
+
 // Constants
 const U_STARTXREF : &[u8] = b"startxref";
 const U_PDF : &[u8] = b"%PDF";
 const U_TRAILER : &[u8] = b"trailer";
 const U_XREF : &[u8] = b"xref";
+const U_OBJ : &[u8] = b"obj";
+const U_STREAM : &[u8] = b"stream";
+const U_ENDSTREAM : &[u8] = b"endstream";
 const C_PREV : &str = "Prev";
 const C_SIZE : &str = "Size";
 
 impl Document {
     /// Read Text Sections From Page
     pub fn get_text_from_page(&mut self, page_nr : usize) -> Option<Vec<Text>>{
-        let Some(page_object) = self.get_page_no(page_nr) else{
+        let Some(page) = self.get_page(page_nr) else{
             return None;
         };
-        
-        // Get Page Fonts
-        let fonts = get_page_resources(self, &page_object);
-        
-        // Get ids of content
-        // If contents is non-existent page is empty
-        let Some(page_contents) = page_object.get_dict_value("Contents") else{
-            return None;
-        };
-        let Some(content_ids) = page_contents.get_usize_array() else{
+        page.get_text(self)
+    }
+
+    /// Returns the number of pages in the document
+    pub fn page_count(&mut self) -> usize{
+        self.load_page_ids();
+        match &self.page_ids {
+            Some(page_ids) => page_ids.len(),
+            None => 0,
+        }
+    }
+
+    /// Returns a page, given a page number (starting at 0)
+    pub fn get_page(&mut self, page_nr : usize) -> Option<Page>{
+        self.load_page_ids();
+        let Some(page_x) = self.page_ids.as_ref()?.get(page_nr) else{
             return None;
         };
-        
-        read_objects_text(self, content_ids, &fonts)
+        Page::from(self, *page_x, page_nr)
+    }
+
+    /// Returns the comments and text markups on all pages
+    pub fn get_notes(&mut self) -> Vec<Note>{
+        let mut notes : Vec<Note> = Vec::new();
+        for page_nr in 0..self.page_count(){
+            if let Some(page) = self.get_page(page_nr){
+                notes.extend(page.get_notes(self));
+            }
+        }
+        notes
     }
 
-    /// Returns a page, given a page number
-    pub(crate) fn get_page_no(&mut self, page_nr : usize) -> Option<PdfVar>{
+    /// Returns the files embedded in the document, such as attachments and portfolio files
+    pub fn get_embedded_files(&mut self) -> Vec<EmbeddedFile>{
+        get_embedded_files(self)
+    }
+
+    /// Returns the decoded content of an embedded file
+    pub fn get_embedded_file_data(&mut self, file : &EmbeddedFile) -> Result<Vec<u8>, PdfError>{
+        get_embedded_file_data(self, file)
+    }
+
+    /// Returns an iterator over a name tree of the catalogs /Names dictionary, such as Dests or EmbeddedFiles.
+    /// The iterator is empty if the tree does not exist
+    pub fn get_name_tree(&mut self, name : &str) -> NameTree<'_>{
+        let root = self.get_catalog()
+            .and_then(|x| x.get_dict_value("Names").cloned())
+            .and_then(|x| self.resolve(&x))
+            .and_then(|x| x.get_dict_value(name).cloned())
+            .unwrap_or(PdfVar::Null);
+        NameTree::new(self, &root)
+    }
+
+    /// Returns the printed label of every page, such as iii or 231. None if the document has no page labels
+    pub fn get_page_labels(&mut self) -> Option<Vec<String>>{
+        get_page_labels(self)
+    }
+
+    /// Returns the fields of the interactive form, with the signatures of signed signature fields
+    pub fn get_form_fields(&mut self) -> Vec<FormField>{
+        get_form_fields(self)
+    }
+
+    /// Returns an iterator over all pages in the document
+    pub fn pages(&mut self) -> Pages<'_>{
+        Pages::new(self)
+    }
+
+    /// Returns the page number (starting at 0) of a page object
+    pub(crate) fn get_page_index(&mut self, page_id : usize) -> Option<usize>{
+        self.load_page_ids();
+        self.page_ids.as_ref()?.iter().position(|x| *x == page_id)
+    }
+
+    /// Returns the document catalog
+    pub(crate) fn get_catalog(&mut self) -> Option<PdfVar>{
+        let root = self.trailer.root;
+        self.get_object_by_id(root)
+    }
+
+    /// Returns the bookmarks of the document outline, in reading order
+    pub fn get_outline(&mut self) -> Vec<OutlineItem>{
+        get_outline(self)
+    }
+
+    /// Builds the list of page ids from the page tree, the list is only built once
+    fn load_page_ids(&mut self){
+        if self.page_ids.is_some(){
+            return;
+        }
         let root = self.trailer.root;
         let mut page_ids : Vec<usize> = Vec::new();
 
-        // Get catalog object
-        let Some(catalog_obj) = self.get_object_by_id(root) else {
-            return None;
-        };
-        
-        // Get pages object
-        let Some(pages_obj) = catalog_obj.get_dict_value("Pages") else{
-            return None;
-        };
-        
-        // Get index of pages object
-        let Some(pages_id) = pages_obj.get_indirect_obj_index() else{
-            return None;
-        };
+        // Get pages object from catalog, and the reference to the pages object
+        let pages_ref = self.get_object_by_id(root)
+            .and_then(|catalog_obj| catalog_obj.get_dict_value("Pages")?.get_object_ref());
 
-        get_page_ids(self, &mut page_ids, pages_id);
-        let Some(page_x) = page_ids.get(page_nr) else{
-            return None;
-        };
-        let Some(o) = self.get_object_by_id(*page_x) else{
-            return None;
-        };
-        Some(o)
+        if let Some(pages_ref) = pages_ref{
+            get_page_ids(self, &mut page_ids, pages_ref);
+        }
+        self.page_ids = Some(page_ids);
     }
 
     /// Returns an object with given id
     /// Unpacks the object if it is in an object stream
     pub(crate) fn get_object_by_id(&mut self, obj_id : usize) -> Option<PdfVar>{
+        // Objects are only parsed once
+        if let Some(object) = self.object_cache.get(&obj_id){
+            return Some(object.clone());
+        }
+
+        // An object that is needed to read itself can't be read, such as an object stream
+        // that contains itself or a stream whose /Length is stored in the same stream
+        if self.loading.contains(&obj_id){
+            self.warn(PdfError::new(ErrorKind::CycleError, "read object").in_object(obj_id));
+            return None;
+        }
+        if self.loading.len() >= self.limits.max_depth{
+            self.warn(PdfError::new(ErrorKind::LimitError, "read object").in_object(obj_id));
+            return None;
+        }
+        self.loading.push(obj_id);
+        let object = self.load_object(obj_id);
+        self.loading.pop();
+        object
+    }
+
+    /// Reads an object that is not in the cache
+    fn load_object(&mut self, obj_id : usize) -> Option<PdfVar>{
         // Fetch the object index in the xref table
-        let (mut xref_1, mut compr_1) = {
+        let (mut xref_1, mut compr_1, mut generation) = {
             let Some(obj_ref) = self.xref.get(obj_id) else{
                 return None;
             };
-            (obj_ref.xref, obj_ref.compressed)
+            (obj_ref.xref, obj_ref.compressed, obj_ref.generation)
         };
         
+        // Free or missing object
+        if compr_1 != 1 && compr_1 != 2{
+            return None;
+        }
+
         // If the object is compressed, decompress it
         if compr_1 != 1{
             unpack_obj_stm(self, xref_1);
@@ -122,6 +345,7 @@ impl Document {
             };
             xref_1 = obj_xref.xref;
             compr_1 = obj_xref.compressed;
+            generation = obj_xref.generation;
             
             // If object is still compressed
             if compr_1 != 1{
@@ -133,18 +357,159 @@ impl Document {
         let object = match PdfVar::from(self, xref_1) {
             Ok(x) => x,
             Err(_) =>{
-                return None;
+                return self.recover_object(obj_id, xref_1);
             }
         };
 
+        // The object found at the offset has to be the requested object, with the same generation
+        if let PdfVar::Object { _id, content } = &object {
+            let header_gen = match content.first() {
+                Some(PdfVar::ObjectRef(_, header_gen)) => *header_gen,
+                _ => generation,
+            };
+            if *_id != obj_id || header_gen != generation{
+                return self.recover_object(obj_id, xref_1);
+            }
+        }
+
+        let mut object = object;
+        decrypt_object(self, xref_1, &mut object);
+        self.object_cache.insert(obj_id, object.clone());
         Some(object)
     }
 
+    /// Returns the object a reference points at. If the object is free, or has been replaced
+    /// by an object with another generation, the reference points at nothing
+    pub(crate) fn get_object(&mut self, obj_id : usize, generation : usize) -> Option<PdfVar>{
+        let obj_ref = self.xref.get(obj_id)?;
+        let current_gen = match obj_ref.compressed {
+            1 => obj_ref.generation,
+            2 => 0,
+            _ => {
+                return None;
+            }
+        };
+        if current_gen != generation{
+            return None;
+        }
+        self.get_object_by_id(obj_id)
+    }
+
+    /// Called when an object could not be read at its xref offset.
+    /// If the offset does not point at the objects header, the xref table is rebuilt and the lookup retried
+    fn recover_object(&mut self, obj_id : usize, offset : usize) -> Option<PdfVar>{
+        // The offset is correct, the object itself is broken
+        if let Some((header_id, _, _)) = read_obj_header(&self.data, offset){
+            if header_id == obj_id{
+                return None;
+            }
+        }
+        
+        // Only rebuild once
+        if self.recovered{
+            return None;
+        }
+        if rebuild_xref(self).is_err(){
+            return None;
+        }
+        self.load_object(obj_id)
+    }
+
+    /// Returns the resource limits of the document
+    pub fn limits(&self) -> &Limits{
+        &self.limits
+    }
+
+    /// Returns the problems that were worked around or skipped when reading the document,
+    /// such as a wrong stream length or a stream over the resource limits
+    pub fn warnings(&self) -> &Vec<PdfError>{
+        &self.warnings
+    }
+
+    /// Records a warning, once for each offset and object
+    pub(crate) fn warn(&mut self, warning : PdfError){
+        let exists = self.warnings.iter().any(|x| {
+            x.kind == warning.kind && x.offset == warning.offset && x.object_id == warning.object_id
+        });
+        if !exists{
+            self.warnings.push(warning);
+        }
+    }
+
+    /// Appends decoded data to the reader data, returns where it starts.
+    /// None if the data would exceed the limit of decoded data kept in memory
+    pub(crate) fn append_data(&mut self, bytes : &[u8], operation : &'static str) -> Option<usize>{
+        let retained = self.data.len() - self.file_size;
+        if bytes.len() > self.limits.max_total_decoded.saturating_sub(retained){
+            self.warn(PdfError::new(ErrorKind::LimitError, operation).at(self.data.len()));
+            return None;
+        }
+        let start = self.data.len();
+        self.data.extend_from_slice(bytes);
+        Some(start)
+    }
+
+    /// Removes data added with append_data once it has been read.
+    /// Kept if other data was appended after it, objects may point at that data
+    pub(crate) fn release_data(&mut self, start : usize, size : usize){
+        if start >= self.file_size && self.data.len() == start + size{
+            self.data.truncate(start);
+        }
+    }
+
+    /// Returns true if the xref table was broken, and had to be rebuilt by scanning the document
+    pub fn is_recovered(&self) -> bool{
+        self.recovered
+    }
+
+    /// Returns the value of an indirect object, or a copy of the value if it is direct.
+    /// Stream objects are returned as the whole object. References to free objects are null
+    pub(crate) fn resolve(&mut self, value : &PdfVar) -> Option<PdfVar>{
+        let PdfVar::IndirectObject(obj_id, generation) = value else{
+            return Some(value.clone());
+        };
+        let Some(object) = self.get_object(*obj_id, *generation) else{
+            return Some(PdfVar::Null);
+        };
+        let PdfVar::Object { _id, content } = &object else{
+            return None;
+        };
+        if let Some(PdfVar::Stream { start: _, size: _ }) = content.get(2){
+            return Some(object);
+        }
+        content.get(1).cloned()
+    }
+
     /// Returns true if document is encrypted
     pub fn is_encrypted(&mut self) -> bool{
         self.trailer.encrypt != 0
     }
 
+    /// Returns true if the document is encrypted, and can't be decrypted without a password
+    pub fn is_locked(&self) -> bool{
+        if self.trailer.encrypt == 0{
+            return false;
+        }
+        match &self.security {
+            Some(handler) => !handler.is_unlocked(),
+            None => true,
+        }
+    }
+
+    /// Decrypts the document with a user or owner password. Returns true if the password is accepted
+    pub fn unlock(&mut self, password : &str) -> bool{
+        let Some(handler) = self.security.as_mut() else{
+            return false;
+        };
+        if !handler.authenticate(password.as_bytes()){
+            return false;
+        }
+
+        // Objects read before the document was unlocked are still encrypted
+        self.object_cache.clear();
+        true
+    }
+
     /// Returns a value from the documents info directory, based on a given key
     pub fn get_info(&mut self, key : &str) -> Option<String>{
         let info_ref = self.trailer.info;
@@ -153,37 +518,167 @@ impl Document {
         info_entry_obj.get_str()
     }
 
+    /// Returns the revisions of the document, the first is the original document.
+    /// Empty if the xref table was broken and had to be rebuilt
+    pub fn revisions(&self) -> &Vec<Revision>{
+        &self.revisions
+    }
+
+    /// Returns the index of the revision the document is viewed as
+    pub fn revision(&self) -> usize{
+        self.revision
+    }
+
+    /// Views the document as it was when the given revision was saved
+    pub fn set_revision(&mut self, revision : usize) -> Result<(),PdfError>{
+        let Some(xref_offset) = self.revisions.get(revision).map(|x| x.xref_offset) else{
+            return Err(PdfError::new(ErrorKind::DocumentError, "set revision"));
+        };
+        let encrypt = self.trailer.encrypt;
+
+        // The current view is kept aside, and put back if the xref of the revision can't be read
+        let xref = std::mem::take(&mut self.xref);
+        let object_cache = std::mem::take(&mut self.object_cache);
+        let page_ids = self.page_ids.take();
+        let trailer = self.trailer.clone();
+
+        // Parsing the xref chain again would add the revisions again
+        let revisions = std::mem::take(&mut self.revisions);
+        let parsed = parse_xref(self, xref_offset, true);
+        self.revisions = revisions;
+        if let Err(e) = parsed{
+            self.xref = xref;
+            self.object_cache = object_cache;
+            self.page_ids = page_ids;
+            self.trailer = trailer;
+            return Err(e);
+        }
+        self.revision = revision;
+
+        // A new encryption dictionary needs a new security handler
+        if self.trailer.encrypt != encrypt{
+            self.security = None;
+            if self.trailer.encrypt != 0{
+                load_security_handler(self);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns all values of the documents Info dictionary
+    pub fn get_document_info(&mut self) -> Option<DocumentInfo>{
+        let info_ref = self.trailer.info;
+        let info_obj = self.get_object_by_id(info_ref)?;
+        let PdfVar::Object { _id, content } = &info_obj else{
+            return None;
+        };
+        let Some(PdfVar::Dictionary(info_dict)) = content.get(1) else{
+            return None;
+        };
+
+        // Values can be indirect objects
+        let mut resolved : HashMap<String, PdfVar> = HashMap::new();
+        for (key, value) in info_dict{
+            if let Some(value) = self.resolve(value){
+                resolved.insert(key.to_string(), value);
+            }
+        }
+        Some(DocumentInfo::from(&resolved))
+    }
+
+    /// Returns the XMP metadata in the /Metadata stream of the catalog
+    pub fn get_xmp(&mut self) -> Option<XmpMetadata>{
+        let catalog = self.get_catalog()?;
+        let (metadata_id, generation) = catalog.get_dict_value("Metadata")?.get_object_ref()?;
+        let metadata_obj = self.get_object(metadata_id, generation)?;
+        let packet = metadata_obj.get_decoded_stream(self)?;
+        Some(XmpMetadata::from_bytes(&packet))
+    }
+
+    /// Reads a document from a file
     pub(crate) fn from(filepath : &str) -> Result<Self, PdfError>{
         let doc_u8: Vec<u8> = match load_document(&filepath) {
             Ok(d) => d,
-            Err(_e) => {
-                return Err(PdfError::LoadError);
+            Err(e) => {
+                return Err(PdfError::new(ErrorKind::LoadError, "read file").caused_by(e));
             }
         };
+        Document::from_bytes(doc_u8)
+    }
+
+    /// Reads a document from a reader, such as stdin or an entry in an archive
+    pub fn from_reader(mut reader : impl Read) -> Result<Self, PdfError>{
+        let mut doc_u8 : Vec<u8> = Vec::new();
+        if let Err(e) = reader.read_to_end(&mut doc_u8){
+            return Err(PdfError::new(ErrorKind::LoadError, "read pdf data").caused_by(e));
+        }
+        Document::from_bytes(doc_u8)
+    }
+
+    /// Reads a document from the bytes of a pdf file
+    pub fn from_bytes(doc_u8 : Vec<u8>) -> Result<Self, PdfError>{
+        Document::from_bytes_with_limits(doc_u8, Limits::default())
+    }
+
+    /// Reads a document from bytes, reading fails with a LimitError if the document needs more resources than allowed
+    pub fn from_bytes_with_limits(doc_u8 : Vec<u8>, limits : Limits) -> Result<Self, PdfError>{
         let xref_table : Vec<ObjectRef> = Vec::new();
-        let trailer : Trailer = Trailer { info: 0, root: 0, size: 0, encrypt: 0 };
-        let mut doc = Document{xref : xref_table, trailer, data : doc_u8, it : 0};
+        let trailer : Trailer = Trailer { info: 0, root: 0, size: 0, encrypt: 0, id: Vec::new() };
+        let mut doc = Document{xref : xref_table, trailer, file_size : doc_u8.len(), data : doc_u8, it : 0, recovered : false, object_cache : HashMap::new(), page_ids : None, security : None, revisions : Vec::new(), revision : 0, warnings : Vec::new(), limits, nesting : 0, loading : Vec::new()};
         
         // Step 1: Look at head, Look for %PDF
         parse_pdf_version(&mut doc)?;
 
         // Step 2: Locate and parse startxref at the end of the file
-        let startxref = read_start_xref(&mut doc)?;
-        
         // Step 3: Parse the xref-table pointed at by startxref
-        parse_xref(&mut doc, startxref, true)?;
+        let parsed = match read_start_xref(&mut doc) {
+            Ok(startxref) => parse_xref(&mut doc, startxref, true),
+            Err(e) => Err(e),
+        };
+
+        // Step 4: If the xref is missing or broken, rebuild it by scanning the document
+        if parsed.is_err() || doc.trailer.root == 0{
+            rebuild_xref(&mut doc)?;
+        }
+
+        // Revisions were read from the newest to the oldest
+        doc.revisions.reverse();
+        for revision in doc.revisions.iter_mut(){
+            revision.changed_ids.sort();
+            revision.changed_ids.dedup();
+        }
+        doc.revision = doc.revisions.len().saturating_sub(1);
+
+        // Step 5: Set up decryption, using the empty user password. A rebuilt xref has already set it up
+        if doc.trailer.encrypt != 0 && doc.security.is_none(){
+            load_security_handler(&mut doc);
+        }
+
+        // Step 6: Read the catalog, a stale offset in the xref triggers a rebuild
+        let root = doc.trailer.root;
+        doc.get_object_by_id(root);
 
         Ok(doc)
     }
 
+    /// Creates an error at the current position in the reader
+    pub(crate) fn error(&self, kind : ErrorKind, operation : &'static str) -> PdfError{
+        PdfError::new(kind, operation).at(self.it)
+    }
+
     /// Returns the size of the reader data, in number of bytes
     pub fn size(&self) -> usize{
         self.data.len()
     }
 
-    /// Returns the u8 char value at the current position in the reader. 
-    pub(crate) fn byte(&self)->u8{
-        return self.data[self.it];
+    /// Returns the size of the pdf file, in number of bytes
+    pub fn file_size(&self) -> usize{
+        self.file_size
+    }
+
+    /// Returns the u8 char value at the current position in the reader, None at the end of the data
+    pub(crate) fn byte(&self)->Option<u8>{
+        return self.data.get(self.it).copied();
     }
 
     /// Moves the documents position (it) forward until first non whitespace char 
@@ -193,10 +688,10 @@ impl Document {
 
     /// Moves the position (it) to a new non empty line
     pub(crate) fn next_line(&mut self){
-        while !matches!(self.byte(), 10 | 13){
+        while !matches!(self.byte(), Some(10 | 13) | None){
             self.it += 1;
         }
-        while matches!(self.byte(), 10 | 13){
+        while matches!(self.byte(), Some(10 | 13)){
             self.it += 1;
         }
     }
@@ -206,7 +701,7 @@ impl Document {
 /// Iterates over vector from ix, returns first non whitespace index
 pub(crate) fn skip_whitespace(doc_u8 : &Vec<u8>, start: usize) -> usize{
     let mut it = start;
-    while doc_u8[it].is_ascii_whitespace(){
+    while it < doc_u8.len() && doc_u8[it].is_ascii_whitespace(){
         it += 1;
     }
     return it;
@@ -226,27 +721,27 @@ fn read_start_xref(doc : &mut Document) -> Result<usize,PdfError>{
     // Locate startxref at end of document
     loop {
         if doc.size()-doc.it > 100 || doc.it == 0{
-            return Err(PdfError::XrefError);
+            return Err(doc.error(ErrorKind::XrefError, "read startxref"));
         }
-        if doc.byte() == b's'{
+        if doc.byte() == Some(b's'){
             if cmp_u8(&doc.data, doc.it, U_STARTXREF){
                 doc.it+=9;
                 break;
             }
-            return Err(PdfError::XrefError);
+            return Err(doc.error(ErrorKind::XrefError, "read startxref"));
         }
         doc.it -= 1;
     }
     doc.skip_whitespace();
 
     // Parse number
-    while doc.byte().is_ascii_digit() {
-        startxref = startxref*10+(doc.byte() as usize)-48;
+    while let Some(digit @ b'0'..=b'9') = doc.byte() {
+        startxref = startxref.saturating_mul(10).saturating_add((digit - 48) as usize);
         doc.it += 1;   
     }
 
     if startxref == 0{
-        return Err(PdfError::XrefError);
+        return Err(doc.error(ErrorKind::XrefError, "read startxref"));
     }
     Ok(startxref)
 }
@@ -254,7 +749,7 @@ fn read_start_xref(doc : &mut Document) -> Result<usize,PdfError>{
 /// Confirms that file begins with %PDF
 fn parse_pdf_version(doc : &mut Document) -> Result<(),PdfError>{
     if !cmp_u8(&doc.data, 0, U_PDF){
-        return Err(PdfError::DocumentError);
+        return Err(doc.error(ErrorKind::DocumentError, "read pdf header"));
     }
     Ok(())
 }
@@ -270,15 +765,35 @@ fn load_document(filepath : &str) -> Result<Vec<u8>, io::Error>{
     Ok(doc)
 }
 
-/// Parse Xref objects and tables
+/// Parse Xref objects and tables, and the previous xref sections they refer to with /Prev
 fn parse_xref(doc : &mut Document, start : usize, createtrailer : bool) -> Result<(),PdfError>{
+    let mut visited : HashSet<usize> = HashSet::new();
+    let mut next = Some(start);
+    let mut createtrailer = createtrailer;
+    while let Some(start) = next{
+        // A /Prev that points back at a section already read would be followed forever
+        if !visited.insert(start){
+            doc.warn(PdfError::new(ErrorKind::CycleError, "follow xref prev").at(start));
+            break;
+        }
+        next = parse_xref_section(doc, start, createtrailer)?;
+        createtrailer = false;
+    }
+    Ok(())
+}
+
+/// Parses one xref object or table, returns the offset of the previous section
+fn parse_xref_section(doc : &mut Document, start : usize, createtrailer : bool) -> Result<Option<usize>,PdfError>{
     doc.it = start;
 
     if doc.size() <= doc.it{
-        return Err(PdfError::XrefError);
+        return Err(doc.error(ErrorKind::XrefError, "parse xref"));
     }
 
-    if doc.byte().is_ascii_digit(){
+    // Every xref section is a revision of the document
+    doc.revisions.push(Revision { xref_offset : start, trailer : Trailer { info: 0, root: 0, size: 0, encrypt: 0, id: Vec::new() }, changed_ids : Vec::new() });
+
+    if doc.byte().is_some_and(|x| x.is_ascii_digit()){
         // The case were the XREF is an object
         let start = doc.it;
         let xref_object = PdfVar::from(doc, start)?;
@@ -289,55 +804,79 @@ fn parse_xref(doc : &mut Document, start : usize, createtrailer : bool) -> Resul
         if createtrailer{
             create_trailer(doc, &xref_object);
         }
-    } else if doc.byte() == b'x'{
+        set_revision_trailer(doc, &xref_object);
+
+        // If the xref contains a /Prev-key, read that previous Xref table
+        Ok(xref_object.get_dict_value(C_PREV).and_then(|x| x.get_indirect_obj_index()))
+    } else if doc.byte() == Some(b'x'){
         // The case were the XREF is only and XREF table, and the trailer is expected after it
-        parse_xref_table(doc)?;
-        parse_table_trailer(doc, createtrailer)?;
+        let free_entries = parse_xref_table(doc)?;
+        parse_table_trailer(doc, createtrailer, free_entries)
     } else{
-        return Err(PdfError::XrefError);
+        Err(doc.error(ErrorKind::XrefError, "parse xref"))
     }
-    Ok(())
 }
 
-fn parse_table_trailer(doc : &mut Document, createtrailer : bool) -> Result<(),PdfError>{
+/// Parses the trailer after an xref table, and the xref data it refers to. Returns the offset of /Prev.
+/// In hybrid files the table is read first, then the /XRefStm stream, then the free entries of the table
+fn parse_table_trailer(doc : &mut Document, createtrailer : bool, free_entries : Vec<(usize, ObjectRef)>) -> Result<Option<usize>,PdfError>{
     // Parse trailer. First verify that next is trailer
     doc.skip_whitespace();
     if !cmp_u8(&doc.data, doc.it, U_TRAILER){
-        return Err(PdfError::XrefError);
+        return Err(doc.error(ErrorKind::XrefError, "parse trailer"));
     }
     doc.it += 7;
     let mut stack : Vec<PdfVar> = Vec::new();
     parse_object(doc, &mut stack)?;
     if stack.len() != 1{
-        return Err(PdfError::XrefError);
+        return Err(doc.error(ErrorKind::XrefError, "parse trailer"));
     }
     let Some(trailer_dict) = stack.get(0) else {
-        return Err(PdfError::XrefError);
+        return Err(doc.error(ErrorKind::XrefError, "parse trailer"));
     };
 
-    
+    set_revision_trailer(doc, trailer_dict);
+
+    // Hybrid file, the compressed objects are listed in an xref stream
+    if let Some(xref_stm_obj) = trailer_dict.get_dict_value("XRefStm"){
+        let Some(xref_stm) = xref_stm_obj.get_indirect_obj_index() else{
+            return Err(doc.error(ErrorKind::XrefError, "parse trailer"));
+        };
+        let xref_object = PdfVar::from(doc, xref_stm)?;
+        parse_xref_object(doc, &xref_object)?;
+    }
+
+    // Free entries in the table don't hide objects in the xref stream
+    for (obj_id, obj_ref) in free_entries{
+        if doc.xref[obj_id].compressed == 3{
+            doc.xref[obj_id] = obj_ref;
+        }
+    }
+
+    let mut prev : Option<usize> = None;
     if let Some(prev_obj) = trailer_dict.get_dict_value(C_PREV){
         match prev_obj.get_indirect_obj_index() {
-            Some(x) => parse_xref(doc, x, false)?,
-            None => return Err(PdfError::XrefError),
+            Some(x) => prev = Some(x),
+            None => return Err(doc.error(ErrorKind::XrefError, "parse trailer")),
         };
     };
     
     if createtrailer{
         create_trailer(doc, trailer_dict);
     }
-    Ok(())
+    Ok(prev)
 }
 
-/// Parses an xref table
-fn parse_xref_table(doc : &mut Document) -> Result<(),PdfError>{
+/// Parses an xref table, the free entries are returned instead of being added to the xref table
+fn parse_xref_table(doc : &mut Document) -> Result<Vec<(usize, ObjectRef)>,PdfError>{
+    let mut free_entries : Vec<(usize, ObjectRef)> = Vec::new();
     if !cmp_u8(&doc.data, doc.it, U_XREF){
-        return Err(PdfError::XrefError);
+        return Err(doc.error(ErrorKind::XrefError, "parse xref table"));
     }
     doc.it += 4;
     doc.skip_whitespace();
-    if !doc.byte().is_ascii_digit(){
-        return Err(PdfError::XrefError);
+    if !doc.byte().is_some_and(|x| x.is_ascii_digit()){
+        return Err(doc.error(ErrorKind::XrefError, "parse xref table"));
     }
 
     loop {
@@ -348,42 +887,55 @@ fn parse_xref_table(doc : &mut Document) -> Result<(),PdfError>{
         doc.skip_whitespace();
         
         let Some((length, _size2)) = read_number(doc) else{
-            return Err(PdfError::XrefError);
+            return Err(doc.error(ErrorKind::XrefError, "parse xref table"));
         };
         
         // Adjust size of xref table
-        let size = index+length;
-        for _ in doc.xref.len()..size{
-            doc.xref.push(ObjectRef { compressed: 3, xref : 0, _version:0});
-        }
+        grow_xref(doc, index.saturating_add(length))?;
 
-        // Read the xref-entries
+        // Read the xref-entries, nnnnnnnnnn ggggg n/f
         for i in index..index+length {
             doc.next_line();
+            add_changed_id(doc, i);
             let Some((num1, num1_size)) = read_number(doc) else{
-                return Err(PdfError::XrefError);
+                return Err(doc.error(ErrorKind::XrefError, "parse xref table"));
             };
             if num1_size != 10{
-                return Err(PdfError::XrefError);
-            }
-            if doc.xref[i].compressed == 3{
-                doc.xref[i] = ObjectRef { compressed: 1, xref : num1, _version:0};
+                return Err(doc.error(ErrorKind::XrefError, "parse xref table"));
             }
+            doc.skip_whitespace();
+            let Some((generation, _)) = read_number(doc) else{
+                return Err(doc.error(ErrorKind::XrefError, "parse xref table"));
+            };
+            doc.skip_whitespace();
+            match doc.byte() {
+                Some(b'n') => {
+                    if doc.xref[i].compressed == 3{
+                        doc.xref[i] = ObjectRef { compressed : 1, xref : num1, generation};
+                    }
+                }
+                Some(b'f') => {
+                    free_entries.push((i, ObjectRef { compressed : 0, xref : num1, generation}));
+                }
+                _ => {
+                    return Err(doc.error(ErrorKind::XrefError, "parse xref table"));
+                }
+            };
         }
         doc.next_line();
     }
-    Ok(())
+    Ok(free_entries)
 }
 
 /// Reads a number, returns (number,numbersize)
 fn read_number(doc : &mut Document) -> Option<(usize,usize)>{
-    if !doc.byte().is_ascii_digit(){
+    if !doc.byte()?.is_ascii_digit(){
         return None;
     }
     let mut size = 0;
-    let mut num = 0;
-    while doc.byte().is_ascii_digit() {
-        num = num*10 + (doc.byte() - 48) as usize;
+    let mut num : usize = 0;
+    while let Some(digit @ b'0'..=b'9') = doc.byte() {
+        num = num.saturating_mul(10).saturating_add((digit - 48) as usize);
         doc.it += 1;
         size += 1;
     }
@@ -391,12 +943,21 @@ fn read_number(doc : &mut Document) -> Option<(usize,usize)>{
     return Some((num,size));
 }
 
+/// Adds unset entries to the xref table until it has the given size
+fn grow_xref(doc : &mut Document, size : usize) -> Result<(),PdfError>{
+    if size > doc.limits.max_objects{
+        return Err(doc.error(ErrorKind::LimitError, "grow xref table"));
+    }
+    for _ in doc.xref.len()..size{
+        doc.xref.push(ObjectRef { compressed: 3, xref : 0, generation:0});
+    }
+    Ok(())
+}
+
 /// Parse the stream of an xref object object
 fn parse_xref_object(doc : &mut Document, xref_object : &PdfVar) -> Result<(),PdfError>{
     // Get decoded stream from xref_object
-    let Some(decoded) = xref_object.get_decoded_stream(doc) else{
-        return Err(PdfError::XrefError);
-    };
+    let decoded = xref_object.decode_stream(doc)?;
 
     // Fetch W, Size and Index
     // W = [1 2 1], How many bytes are in each column
@@ -404,11 +965,11 @@ fn parse_xref_object(doc : &mut Document, xref_object : &PdfVar) -> Result<(),Pd
         Some(x) => match x.get_usize_array(){
             Some(x) => x,
             None => {
-                return Err(PdfError::XrefError)
+                return Err(doc.error(ErrorKind::XrefError, "parse xref stream"))
             },
         }
         None => {
-            return Err(PdfError::XrefError);
+            return Err(doc.error(ErrorKind::XrefError, "parse xref stream"));
         }
     };
     
@@ -417,11 +978,11 @@ fn parse_xref_object(doc : &mut Document, xref_object : &PdfVar) -> Result<(),Pd
         Some(x) => match x.get_indirect_obj_index(){
             Some(x) => x,
             None => {
-                return Err(PdfError::XrefError);
+                return Err(doc.error(ErrorKind::XrefError, "parse xref stream"));
             }
         }
         None => {
-            return Err(PdfError::XrefError);
+            return Err(doc.error(ErrorKind::XrefError, "parse xref stream"));
         }
     };
     
@@ -430,25 +991,28 @@ fn parse_xref_object(doc : &mut Document, xref_object : &PdfVar) -> Result<(),Pd
         Some(x) => match x.get_usize_array(){
             Some(x) => x,
             None =>{
-                return Err(PdfError::XrefError)
+                return Err(doc.error(ErrorKind::XrefError, "parse xref stream"))
             }
         }
         None => vec![0,size],
     };
     
+    // Three columns of at most 8 bytes each, larger values don't fit in a usize
+    if w.len() != 3 || w.iter().any(|x| *x > 8){
+        return Err(doc.error(ErrorKind::XrefError, "parse xref stream"));
+    }
+
     // Declare variables
     let mut decoded_pos : usize = 0;
     let mut iw : usize = 0;
     let cols : usize = w.iter().sum();
     
     // Create new entries in xref_table if needed
-    for _ in doc.xref.len()..size{
-        doc.xref.push(ObjectRef { compressed: 3, xref : 0, _version:0});
-    }
+    grow_xref(doc, size)?;
     
     // Has to be even, given [index size index size...]- pattern
     if index.len()%2 != 0{
-        return Err(PdfError::XrefError);
+        return Err(doc.error(ErrorKind::XrefError, "parse xref stream"));
     }
     
     // Interpret the decoded byte stream as xref
@@ -456,7 +1020,12 @@ fn parse_xref_object(doc : &mut Document, xref_object : &PdfVar) -> Result<(),Pd
         let object_index = index[iw];
         let list_size = index[iw+1];
 
+        // Index can cover objects beyond Size
+        grow_xref(doc, object_index.saturating_add(list_size))?;
+
         for i in object_index..object_index+list_size {
+            add_changed_id(doc, i);
+
             // If object has already been read.
             if doc.xref[i].compressed != 3{
                 decoded_pos += cols;
@@ -464,32 +1033,122 @@ fn parse_xref_object(doc : &mut Document, xref_object : &PdfVar) -> Result<(),Pd
             }
 
             if decoded_pos + cols > decoded.len(){
-                return Err(PdfError::XrefError);
+                return Err(doc.error(ErrorKind::XrefError, "parse xref stream"));
             }
 
             let w1 = get_256_repr(&decoded[decoded_pos..decoded_pos+w[0]]);
             let w2 = get_256_repr(&decoded[decoded_pos+w[0]..decoded_pos+w[0]+w[1]]);
             let w3 = get_256_repr(&decoded[decoded_pos+w[0]+w[1]..decoded_pos+cols]);
-            doc.xref[i] = ObjectRef{compressed : w1 as u8, xref : w2, _version : w3};
+            doc.xref[i] = ObjectRef{compressed : w1 as u8, xref : w2, generation : w3};
             decoded_pos += cols;
         }
 
         iw += 2;
     }
     
-    // If the xref contains a /Prev-key, read that previous Xref table
-    if let Some(prev_obj) = xref_object.get_dict_value(C_PREV){
-        if let Some(prev) = prev_obj.get_indirect_obj_index(){
-            parse_xref(doc, prev, false)?;
-        };
+    Ok(())
+}
+
+
+/// Reads the Encrypt dictionary, and tries to unlock the document with the empty user password
+fn load_security_handler(doc : &mut Document){
+    let encrypt_id = doc.trailer.encrypt;
+    let file_id = doc.trailer.id.clone();
+    if let Some(mut handler) = SecurityHandler::from(doc, encrypt_id, file_id){
+        handler.authenticate(b"");
+        doc.security = Some(handler);
+    }
+    doc.object_cache.clear();
+}
+
+/// Decrypts the strings and the stream of an object read from the file
+fn decrypt_object(doc : &mut Document, offset : usize, object : &mut PdfVar){
+    let Some(handler) = &doc.security else{
+        return;
+    };
+    let PdfVar::Object { _id, content } = object else{
+        return;
     };
 
-    Ok(())
+    // The encrypt dictionary and objects unpacked from object streams are not encrypted
+    if !handler.is_unlocked() || *_id == handler.encrypt_id || offset >= handler.data_len{
+        return;
+    }
+    let Some((_, gen, _)) = read_obj_header(&doc.data, offset) else{
+        return;
+    };
+
+    // Xref streams are never encrypted, metadata streams are optional
+    let mut decrypt_stream = true;
+    if let Some(obj_type) = content.get(1).and_then(|x| x.get_dict_value("Type")).and_then(|x| x.get_name()){
+        match obj_type.as_str() {
+            "XRef" => return,
+            "Metadata" => decrypt_stream = handler.encrypts_metadata(),
+            _ => {}
+        }
+    }
+
+    // Decrypt strings
+    for value in content.iter_mut().skip(1){
+        decrypt_strings(handler, *_id, gen, value);
+    }
+
+    // Decrypt stream, the decrypted stream is appended to the document
+    let Some(PdfVar::Stream { start, size }) = content.get(2) else{
+        return;
+    };
+    if !decrypt_stream || start+size > doc.data.len(){
+        return;
+    }
+    let decrypted = handler.decrypt_stream(*_id, gen, &doc.data[*start..*start+*size]);
+    let Some(decrypted_start) = doc.append_data(&decrypted, "decrypt stream") else{
+        return;
+    };
+    content[2] = PdfVar::Stream { start: decrypted_start, size: decrypted.len() };
 }
 
+/// Decrypts all strings in a value, and in the arrays and dictionaries it contains
+fn decrypt_strings(handler : &SecurityHandler, obj_id : usize, gen : usize, value : &mut PdfVar){
+    match value {
+        PdfVar::StringLiteral(chars) => {
+            let bytes : Vec<u8> = chars.iter().map(|x| *x as u8).collect();
+            *chars = handler.decrypt_string(obj_id, gen, &bytes).iter().map(|x| *x as u32).collect();
+        }
+        PdfVar::Array(array) => {
+            for elem in array{
+                decrypt_strings(handler, obj_id, gen, elem);
+            }
+        }
+        PdfVar::Dictionary(dict) => {
+            for elem in dict.values_mut(){
+                decrypt_strings(handler, obj_id, gen, elem);
+            }
+        }
+        _ => {}
+    }
+}
 
 /// Creates and stores the PDF-trailer
 fn create_trailer(doc : &mut Document, xref_obj : &PdfVar){
+    doc.trailer = read_trailer(xref_obj);
+}
+
+/// Sets the trailer of the revision being parsed
+fn set_revision_trailer(doc : &mut Document, xref_obj : &PdfVar){
+    if let Some(revision) = doc.revisions.last_mut(){
+        revision.trailer = read_trailer(xref_obj);
+    }
+}
+
+/// Adds an object id to the changed ids of the revision being parsed
+fn add_changed_id(doc : &mut Document, obj_id : usize){
+    if let Some(revision) = doc.revisions.last_mut(){
+        revision.changed_ids.push(obj_id);
+    }
+}
+
+/// Reads the trailer values from a trailer dictionary or an xref stream
+fn read_trailer(xref_obj : &PdfVar) -> Trailer{
     let fields = ["Info", "Root", C_SIZE, "Encrypt"];
     let mut cnt : Vec<usize> = Vec::new();
     for f in fields{
@@ -507,64 +1166,285 @@ fn create_trailer(doc : &mut Document, xref_obj : &PdfVar){
             }
         }
     }
-    doc.trailer.info = cnt[0];
-    doc.trailer.root = cnt[1];
-    doc.trailer.size = cnt[2];
-    doc.trailer.encrypt = cnt[3];
+    let mut trailer = Trailer { info: cnt[0], root: cnt[1], size: cnt[2], encrypt: cnt[3], id: Vec::new() };
+
+    // File identifier, used for decryption
+    if let Some(PdfVar::Array(id_array)) = xref_obj.get_dict_value("ID"){
+        if let Some(PdfVar::StringLiteral(id)) = id_array.first(){
+            trailer.id = id.iter().map(|x| *x as u8).collect();
+        }
+    }
+    trailer
 }
 
 
-/// Adds found page id:s to the page_ids vector
-fn get_page_ids(doc : &mut Document, page_ids : &mut Vec<usize>, obj_id : usize){
-    // Fetch object
-    let Some(object) = doc.get_object_by_id(obj_id) else{
-        return;
-    };
-    
-    // Fetch object type
-    let Some(obj_type) = object.get_dict_value("Type") else{
-        return;
-    };
-    
-    // Fetch type as a string
-    let Some(obj_name) = obj_type.get_name() else{
-        return;
-    };
+/// Adds found page id:s to the page_ids vector, in the order of the page tree
+fn get_page_ids(doc : &mut Document, page_ids : &mut Vec<usize>, root_ref : (usize, usize)){
+    // Stack of (node id, generation, depth), the next node to visit is last
+    let mut stack : Vec<(usize, usize, usize)> = vec![(root_ref.0, root_ref.1, 0)];
+    let mut visited : HashSet<usize> = HashSet::new();
 
-    match obj_name.as_str() {
-        "Pages" => {
-            let Some(kids_obj) = object.get_dict_value("Kids") else{
-                return;
-            };
-            let Some(kids_ids) = kids_obj.get_usize_array() else {
-                return;
-            };
-            for kid in kids_ids{
-                get_page_ids(doc, page_ids, kid);
+    while let Some((obj_id, generation, depth)) = stack.pop(){
+        if depth >= doc.limits.max_depth{
+            doc.warn(PdfError::new(ErrorKind::LimitError, "read page tree").in_object(obj_id));
+            continue;
+        }
+
+        // Fetch object
+        let Some(object) = doc.get_object(obj_id, generation) else{
+            continue;
+        };
+
+        // A kid that refers to an ancestor would add the same pages again
+        if !visited.insert(obj_id){
+            doc.warn(PdfError::new(ErrorKind::CycleError, "read page tree").in_object(obj_id));
+            continue;
+        }
+        
+        // Fetch type as a string
+        let Some(obj_name) = object.get_dict_value("Type").and_then(|x| x.get_name()) else{
+            continue;
+        };
+
+        match obj_name.as_str() {
+            "Pages" => {
+                let Some(kids_refs) = object.get_dict_value("Kids").and_then(|x| x.get_object_ref_array()) else{
+                    continue;
+                };
+                // Reversed so the first kid is visited first
+                for (kid_id, kid_gen) in kids_refs.into_iter().rev(){
+                    stack.push((kid_id, kid_gen, depth+1));
+                }
+            }
+            "Page" => {
+                page_ids.push(obj_id);
+            }
+            _ => {
+                // Unknown type
+            }
+        };
+    }
+}
+
+/// Reads an object header (N G obj) at index, returns (object id, generation, index after header)
+fn read_obj_header(doc_u8 : &Vec<u8>, start : usize) -> Option<(usize, usize, usize)>{
+    let mut it = skip_whitespace(doc_u8, start);
+    let mut numbers : [usize; 2] = [0, 0];
+
+    // Read object id and generation, both have to be followed by whitespace
+    for number in numbers.iter_mut(){
+        let num_start = it;
+        while it < doc_u8.len() && doc_u8[it].is_ascii_digit(){
+            *number = number.checked_mul(10)?.checked_add((doc_u8[it] - 48) as usize)?;
+            it += 1;
+        }
+        if it == num_start || it >= doc_u8.len() || !doc_u8[it].is_ascii_whitespace(){
+            return None;
+        }
+        it = skip_whitespace(doc_u8, it);
+    }
+
+    if !cmp_u8(doc_u8, it, U_OBJ){
+        return None;
+    }
+    it += 3;
+    if it < doc_u8.len() && !is_delimiter(doc_u8, it){
+        return None;
+    }
+    Some((numbers[0], numbers[1], it))
+}
+
+/// Rebuilds the xref table and the trailer by scanning the whole document for objects and trailers.
+/// Used when startxref is missing or the xref offsets are wrong
+fn rebuild_xref(doc : &mut Document) -> Result<(),PdfError>{
+    doc.recovered = true;
+    doc.revisions.clear();
+    doc.revision = 0;
+    doc.xref.clear();
+    doc.object_cache.clear();
+    doc.page_ids = None;
+    doc.trailer = Trailer { info: 0, root: 0, size: 0, encrypt: 0, id: Vec::new() };
+    let mut trailer_dict : Option<PdfVar> = None;
+    let mut ix = 0;
+
+    // Step 1: Locate all object headers and trailer dictionaries
+    // Only the file is scanned, decoded data appended after it is not part of the file
+    let file_size = doc.file_size();
+    while ix < file_size{
+        let at_token_start = ix == 0 || is_delimiter(&doc.data, ix-1);
+        let byte = doc.data[ix];
+
+        if at_token_start && byte.is_ascii_digit(){
+            // Object header, later objects replace earlier ones (incremental updates)
+            // An object id can't be larger than the document size, it would be garbage
+            if let Some((obj_id, generation, end)) = read_obj_header(&doc.data, ix).filter(|x| x.0 < file_size){
+                grow_xref(doc, obj_id+1)?;
+                doc.xref[obj_id] = ObjectRef { compressed: 1, xref : ix, generation};
+                ix = end;
+                continue;
+            }
+        } else if at_token_start && byte == b's' && cmp_u8(&doc.data, ix, U_STREAM){
+            // Skip stream content, binary data could look like object headers
+            ix += U_STREAM.len();
+            while ix < file_size && !cmp_u8(&doc.data, ix, U_ENDSTREAM){
+                ix += 1;
+            }
+            continue;
+        } else if at_token_start && byte == b't' && cmp_u8(&doc.data, ix, U_TRAILER){
+            // Trailer dictionary, the last one with a Root entry is used
+            doc.it = ix + U_TRAILER.len();
+            doc.skip_whitespace();
+            let mut stack : Vec<PdfVar> = Vec::new();
+            if doc.it < file_size && parse_object(doc, &mut stack).is_ok(){
+                if let Some(dict) = stack.pop(){
+                    if dict.get_dict_value("Root").is_some(){
+                        trailer_dict = Some(dict);
+                    }
+                }
             }
         }
-        "Page" => {
-            page_ids.push(obj_id);
+        ix += 1;
+    }
+
+    // Step 2: Inspect the found objects, for xref streams, object streams and the catalog
+    let mut catalog_id : usize = 0;
+    let mut xref_stm_offset : usize = 0;
+    let mut obj_streams : Vec<usize> = Vec::new();
+    for obj_id in 0..doc.xref.len(){
+        if doc.xref[obj_id].compressed != 1{
+            continue;
         }
-        _ => {
-            // Unknown type
+        let offset = doc.xref[obj_id].xref;
+        let Ok(object) = PdfVar::from(doc, offset) else{
+            continue;
+        };
+        let Some(obj_type) = object.get_dict_value("Type").and_then(|x| x.get_name()) else{
+            continue;
+        };
+        match obj_type.as_str() {
+            // Without a trailer dictionary, the last xref stream holds the trailer
+            "XRef" if !matches!(trailer_dict, Some(PdfVar::Dictionary(_))) && offset >= xref_stm_offset
+                && object.get_dict_value("Root").is_some() => {
+                xref_stm_offset = offset;
+                trailer_dict = Some(object);
+            }
+            "ObjStm" => {
+                obj_streams.push(obj_id);
+            }
+            "Catalog" => {
+                catalog_id = obj_id;
+            }
+            _ => {}
         }
-    };
+    }
+
+    // Step 3: Create the trailer, an encrypted document needs its security handler to read object streams
+    if let Some(dict) = trailer_dict{
+        create_trailer(doc, &dict);
+    }
+    if doc.trailer.encrypt != 0 && doc.security.is_none(){
+        load_security_handler(doc);
+    }
+
+    // Step 4: Objects only found inside object streams are referred to the stream
+    for stm_id in obj_streams{
+        let Some(stream_obj) = doc.get_object_by_id(stm_id) else{
+            continue;
+        };
+        let Some(first) = stream_obj.get_dict_int("First") else{
+            continue;
+        };
+        let Some(stream_decompr) = stream_obj.get_decoded_stream(doc) else{
+            continue;
+        };
+        let obj_nums = parse_obj_stm_header(&stream_decompr, first);
+        let mut ix = 0;
+        while ix+1 < obj_nums.len(){
+            let obj_id = obj_nums[ix];
+            if obj_id >= doc.size(){
+                break;
+            }
+            grow_xref(doc, obj_id+1)?;
+            if doc.xref[obj_id].compressed == 3{
+                doc.xref[obj_id] = ObjectRef { compressed: 2, xref : stm_id, generation:ix/2};
+            }
+            ix += 2;
+        }
+    }
+
+    // Step 5: Without a trailer, the catalog found in the scan is the root
+    if doc.trailer.root == 0{
+        doc.trailer.root = catalog_id;
+    }
+    if doc.trailer.root == 0{
+        return Err(doc.error(ErrorKind::XrefError, "rebuild xref"));
+    }
+    doc.trailer.size = doc.xref.len();
+    Ok(())
+}
+
+/// Reads the pairs of object ids and offsets in the head of a decoded ObjStm
+fn parse_obj_stm_header(stream_decompr : &Vec<u8>, first : usize) -> Vec<usize>{
+    let mut ix : usize = 0;
+    let mut obj_nums : Vec<usize> = Vec::new();
+    while ix < first && ix < stream_decompr.len(){
+        // Skip whitespace
+        ix = skip_whitespace(stream_decompr, ix);
+        if ix >= stream_decompr.len() || !stream_decompr[ix].is_ascii_digit(){
+            break;
+        }
+
+        // Parse number
+        let mut num : usize = 0;
+        while ix < stream_decompr.len() && stream_decompr[ix].is_ascii_digit() {
+            num = num.saturating_mul(10).saturating_add((stream_decompr[ix] - 48) as usize);
+            ix += 1;
+        }
+
+        obj_nums.push(num);
+    }
+    obj_nums
 }
 
 /// Tris to decode an ObjStm and append the decoded values to the document
 /// Updates the xref-table for the objects in the ObjStm
 fn unpack_obj_stm(doc : &mut Document, obj_id : usize){
-    let Some(stream_obj) = doc.get_object_by_id(obj_id) else{
-        return;
-    };
-    
-    // Handle extends, when one ObjStm refers to another one
-    if let Some(ext_obj) = stream_obj.get_dict_value("Extends"){
+    // Handle extends, when one ObjStm refers to another one. The extended streams are unpacked first
+    let mut chain : Vec<usize> = vec![obj_id];
+    let mut stm_id = obj_id;
+    loop {
+        let Some(stream_obj) = doc.get_object_by_id(stm_id) else{
+            return;
+        };
+        let Some(ext_obj) = stream_obj.get_dict_value("Extends") else{
+            break;
+        };
         let Some(ext_id) = ext_obj.get_indirect_obj_index() else {
             return;
         };
-        unpack_obj_stm(doc, ext_id);
+
+        // The /Extends chain ends if it returns to a stream already in it
+        if chain.contains(&ext_id){
+            doc.warn(PdfError::new(ErrorKind::CycleError, "follow object stream extends").in_object(stm_id));
+            break;
+        }
+        if chain.len() >= doc.limits.max_depth{
+            doc.warn(PdfError::new(ErrorKind::LimitError, "follow object stream extends").in_object(stm_id));
+            break;
+        }
+        chain.push(ext_id);
+        stm_id = ext_id;
+    }
+
+    for stm_id in chain.into_iter().rev(){
+        read_obj_stm(doc, stm_id);
+    }
+}
+
+/// Appends the objects of one ObjStm to the document
+fn read_obj_stm(doc : &mut Document, obj_id : usize){
+    let Some(stream_obj) = doc.get_object_by_id(obj_id) else{
+        return;
     };
 
     // First value
@@ -576,57 +1456,47 @@ fn unpack_obj_stm(doc : &mut Document, obj_id : usize){
         return;
     };
 
-    let mut ix : usize = 0;
-    let mut obj_nums : Vec<usize> = Vec::new();
-    while ix < first{
-        // Skip whitespace
-        while stream_decompr[ix].is_ascii_whitespace(){
-            ix += 1;
-        }
-        
-        if !stream_decompr[ix].is_ascii_digit(){
-            break;
-        }
-
-        // Parse number
-        let mut num = 0;
-        while stream_decompr[ix].is_ascii_digit() {
-            num = num*10 + (stream_decompr[ix] - 48) as usize;
-            ix += 1;
-        }
-
-        obj_nums.push(num);
-    }
+    let obj_nums = parse_obj_stm_header(&stream_decompr, first);
     
-    ix = 1;
+    let mut ix : usize = 1;
     while ix < obj_nums.len(){
         // Declare var
-        let xref_start = doc.size();
         let ix_obj_id = obj_nums[ix-1];
-        let obj_start = obj_nums[ix]+first;
+        let obj_start = obj_nums[ix].saturating_add(first);
 
         // The xref for obj_id should point to this ObjStm, otherwise we dont care
-        if let Some(obj_xref) = doc.xref.get(ix_obj_id){
-            if obj_xref.xref != obj_id {
-                ix += 2;
-                continue;
-            }
+        let Some(obj_xref) = doc.xref.get(ix_obj_id) else{
+            ix += 2;
+            continue;
         };
+        if obj_xref.compressed != 2 || obj_xref.xref != obj_id {
+            ix += 2;
+            continue;
+        }
 
         // Read forward to get where the object ends
         let mut obj_end = stream_decompr.len();
         if ix +2 < obj_nums.len(){
-            obj_end = obj_nums[ix+2]+first;
+            obj_end = obj_nums[ix+2].saturating_add(first);
         }
+
+        // Offsets are read from the stream, and can be outside of it
+        let Some(obj_content) = stream_decompr.get(obj_start..obj_end) else{
+            doc.warn(PdfError::new(ErrorKind::ObjectError, "unpack object stream").in_object(ix_obj_id));
+            ix += 2;
+            continue;
+        };
         
         // Append object to documents byte vector
-        let id_bytes : Vec<u8> = ix_obj_id.to_string().bytes().collect();
-        doc.data.extend(id_bytes);
-        doc.data.extend(b" 0 obj\n");
-        doc.data.extend_from_slice(&stream_decompr[obj_start..obj_end]);
-        doc.data.extend(b"endobj\n\n");
+        let mut obj_bytes : Vec<u8> = ix_obj_id.to_string().bytes().collect();
+        obj_bytes.extend(b" 0 obj\n");
+        obj_bytes.extend_from_slice(obj_content);
+        obj_bytes.extend(b"endobj\n\n");
+        let Some(xref_start) = doc.append_data(&obj_bytes, "unpack object stream") else{
+            return;
+        };
 
-        doc.xref[ix_obj_id] = ObjectRef { compressed: 1, xref : xref_start, _version:0};
+        doc.xref[ix_obj_id] = ObjectRef { compressed: 1, xref : xref_start, generation:0};
         ix += 2;
     }
 }
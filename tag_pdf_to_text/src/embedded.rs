@@ -0,0 +1,104 @@
+use crate::document::{Document, ErrorKind, PdfError};
+use crate::info::PdfDate;
+use crate::pdf_object::PdfVar;
+
+/// A file embedded in the document, from the /EmbeddedFiles name tree or a FileAttachment annotation
+#[derive(Debug, Clone)]
+pub struct EmbeddedFile {
+    pub name : String, // File name of the file specification
+    pub description : Option<String>, // /Desc
+    pub mime_type : Option<String>, // /Subtype of the embedded file stream, application/pdf, ...
+    pub size : Option<usize>, // Uncompressed size in bytes, as given in /Params
+    pub checksum : Option<String>, // MD5 of the uncompressed file, as hex
+    pub creation_date : Option<PdfDate>,
+    pub mod_date : Option<PdfDate>,
+    pub page_index : Option<usize>, // Page of the FileAttachment annotation, None for the name tree
+    pub(crate) stream_id : usize, // Object id of the embedded file stream
+}
+
+impl EmbeddedFile {
+    /// Reads a file specification dictionary, None if it has no embedded file stream
+    pub(crate) fn from(doc : &mut Document, filespec_obj : &PdfVar, key : Option<String>, page_index : Option<usize>) -> Option<Self>{
+        let filespec = doc.resolve(filespec_obj)?;
+
+        // The stream is in /EF, under the same key as the file name
+        let ef = doc.resolve(filespec.get_dict_value("EF")?)?;
+        let (stream_id, stream_gen) = ["UF", "F"].iter()
+            .find_map(|x| ef.get_dict_value(x))
+            .and_then(|x| x.get_object_ref())?;
+        let stream_obj = doc.get_object(stream_id, stream_gen)?;
+
+        // Unicode file name is preferred, the name tree key is a fallback
+        let name = ["UF", "F"].iter()
+            .find_map(|x| filespec.get_dict_str(x, doc))
+            .or(key)
+            .unwrap_or_default();
+        let description = filespec.get_dict_str("Desc", doc);
+        let mime_type = stream_obj.get_dict_value("Subtype").and_then(|x| x.get_name());
+
+        // Size, checksum and dates are optional
+        let mut size : Option<usize> = None;
+        let mut checksum : Option<String> = None;
+        let mut creation_date : Option<PdfDate> = None;
+        let mut mod_date : Option<PdfDate> = None;
+        if let Some(params) = stream_obj.get_dict_value("Params").and_then(|x| doc.resolve(x)){
+            size = params.get_dict_value("Size").and_then(|x| x.get_usize(doc));
+            if let Some(PdfVar::StringLiteral(bytes)) = params.get_dict_value("CheckSum").and_then(|x| doc.resolve(x)){
+                checksum = Some(bytes.iter().map(|x| format!("{:02x}", x)).collect());
+            }
+            creation_date = params.get_dict_str("CreationDate", doc).and_then(|x| PdfDate::parse(&x));
+            mod_date = params.get_dict_str("ModDate", doc).and_then(|x| PdfDate::parse(&x));
+        }
+
+        Some(EmbeddedFile { name, description, mime_type, size, checksum, creation_date, mod_date, page_index, stream_id })
+    }
+
+    /// Returns true if the embedded file is a pdf, by its MIME type or its file name
+    pub fn is_pdf(&self) -> bool{
+        self.mime_type.as_deref() == Some("application/pdf") || self.name.to_lowercase().ends_with(".pdf")
+    }
+}
+
+/// Returns the embedded files of the /EmbeddedFiles name tree and of FileAttachment annotations.
+/// A file that is in both is only returned once
+pub(crate) fn get_embedded_files(doc : &mut Document) -> Vec<EmbeddedFile>{
+    let mut files : Vec<EmbeddedFile> = Vec::new();
+
+    // Step 1: Name tree in the /Names dictionary of the catalog
+    let entries : Vec<(String, PdfVar)> = doc.get_name_tree("EmbeddedFiles").collect();
+    for (key, filespec) in entries{
+        if let Some(file) = EmbeddedFile::from(doc, &filespec, Some(key), None){
+            files.push(file);
+        }
+    }
+
+    // Step 2: File attachment annotations on the pages
+    for page_nr in 0..doc.page_count(){
+        let Some(page) = doc.get_page(page_nr) else{
+            continue;
+        };
+        for annotation in page.get_annotations(doc){
+            if annotation.subtype != "FileAttachment"{
+                continue;
+            }
+            let Some(filespec) = annotation.get_dict_value("FS") else{
+                continue;
+            };
+            let Some(file) = EmbeddedFile::from(doc, filespec, None, Some(page_nr)) else{
+                continue;
+            };
+            if !files.iter().any(|x| x.stream_id == file.stream_id){
+                files.push(file);
+            }
+        }
+    }
+    files
+}
+
+/// Returns the decoded content of an embedded file
+pub(crate) fn get_embedded_file_data(doc : &mut Document, file : &EmbeddedFile) -> Result<Vec<u8>, PdfError>{
+    let Some(stream_obj) = doc.get_object_by_id(file.stream_id) else{
+        return Err(PdfError::new(ErrorKind::ObjectError, "read embedded file").in_object(file.stream_id));
+    };
+    stream_obj.decode_stream(doc)
+}
@@ -0,0 +1,159 @@
+use std::collections::HashSet;
+
+use crate::document::Document;
+use crate::info::PdfDate;
+use crate::pdf_object::PdfVar;
+
+/// A field of the documents interactive form
+#[derive(Debug, Clone)]
+pub struct FormField {
+    pub name : String, // Fully qualified name, parent.child
+    pub field_type : Option<String>, // Btn, Tx, Ch or Sig
+    pub value : Option<String>, // Text of the value, options of a list are joined with ", "
+    pub signature : Option<Signature>, // Value of a signed signature field
+}
+
+/// A digital signature, from the signature dictionary of a signature field
+#[derive(Debug, Clone)]
+pub struct Signature {
+    pub signer : Option<String>, // /Name
+    pub signing_time : Option<PdfDate>, // /M
+    pub reason : Option<String>,
+    pub location : Option<String>,
+    pub contact_info : Option<String>,
+    pub sub_filter : Option<String>, // Format of the signature, adbe.pkcs7.detached, ETSI.CAdES.detached, ...
+    pub byte_range : Vec<(usize, usize)>, // (offset, length) of the signed parts of the file
+    pub covers_whole_file : bool, // False if the file was changed after signing
+}
+
+impl Signature {
+    /// Reads a signature dictionary
+    fn from(doc : &mut Document, sig_dict : &PdfVar) -> Self{
+        let mut byte_range : Vec<(usize, usize)> = Vec::new();
+        if let Some(PdfVar::Array(values)) = sig_dict.get_dict_value("ByteRange").and_then(|x| doc.resolve(x)){
+            let values : Vec<usize> = values.iter().filter_map(|x| x.get_usize(doc)).collect();
+            for range in values.chunks_exact(2){
+                byte_range.push((range[0], range[1]));
+            }
+        }
+
+        // The ranges cover everything from the start to the end of the file, except the signature itself
+        let covers_whole_file = match (byte_range.first(), byte_range.last()) {
+            (Some(first), Some(last)) => first.0 == 0 && last.0.checked_add(last.1) == Some(doc.file_size()),
+            _ => false,
+        };
+
+        Signature {
+            signer : sig_dict.get_dict_str("Name", doc),
+            signing_time : sig_dict.get_dict_str("M", doc).and_then(|x| PdfDate::parse(&x)),
+            reason : sig_dict.get_dict_str("Reason", doc),
+            location : sig_dict.get_dict_str("Location", doc),
+            contact_info : sig_dict.get_dict_str("ContactInfo", doc),
+            sub_filter : sig_dict.get_dict_value("SubFilter").and_then(|x| x.get_name()),
+            byte_range,
+            covers_whole_file,
+        }
+    }
+}
+
+/// A field waiting to be read, with the values it inherits from its parent
+struct PendingField {
+    field_ref : PdfVar,
+    parent_name : String,
+    inherited_type : Option<String>,
+    inherited_value : Option<PdfVar>,
+    depth : usize,
+}
+
+/// Reads the fields of the /AcroForm dictionary of the catalog, in the order of the field tree.
+/// Only terminal fields are returned, with their inherited type and value
+pub(crate) fn get_form_fields(doc : &mut Document) -> Vec<FormField>{
+    let mut fields : Vec<FormField> = Vec::new();
+    let Some(catalog) = doc.get_catalog() else{
+        return fields;
+    };
+    let Some(acro_form) = catalog.get_dict_value("AcroForm").and_then(|x| doc.resolve(x)) else{
+        return fields;
+    };
+    let Some(PdfVar::Array(root_fields)) = acro_form.get_dict_value("Fields").and_then(|x| doc.resolve(x)) else{
+        return fields;
+    };
+
+    // Reversed so the first field is read first
+    let mut stack : Vec<PendingField> = Vec::new();
+    for field_ref in root_fields.iter().rev(){
+        stack.push(PendingField { field_ref : field_ref.clone(), parent_name : String::new(), inherited_type : None, inherited_value : None, depth : 0 });
+    }
+    let mut visited : HashSet<usize> = HashSet::new();
+
+    while let Some(PendingField { field_ref, parent_name, inherited_type, inherited_value, depth }) = stack.pop(){
+        // A kid that refers back to one of its ancestors is only read once
+        if depth >= doc.limits.max_depth{
+            continue;
+        }
+        if let PdfVar::IndirectObject(field_id, _) = field_ref{
+            if !visited.insert(field_id){
+                continue;
+            }
+        }
+        let Some(field) = doc.resolve(&field_ref) else{
+            continue;
+        };
+
+        // Partial names are joined with a period
+        let mut name = parent_name;
+        if let Some(partial_name) = field.get_dict_str("T", doc){
+            if !name.is_empty(){
+                name.push('.');
+            }
+            name.push_str(&partial_name);
+        }
+        let field_type = field.get_dict_value("FT").and_then(|x| x.get_name()).or(inherited_type);
+        let value = field.get_dict_value("V").cloned().or(inherited_value);
+
+        // Kids with a name are fields, kids without a name are only the widgets of this field
+        let mut kid_fields : Vec<PdfVar> = Vec::new();
+        if let Some(PdfVar::Array(kids)) = field.get_dict_value("Kids").and_then(|x| doc.resolve(x)){
+            for kid_ref in kids.iter(){
+                let has_name = doc.resolve(kid_ref).is_some_and(|x| x.get_dict_value("T").is_some());
+                if has_name{
+                    kid_fields.push(kid_ref.clone());
+                }
+            }
+        }
+        if !kid_fields.is_empty(){
+            for kid_ref in kid_fields.into_iter().rev(){
+                stack.push(PendingField { field_ref : kid_ref, parent_name : name.clone(), inherited_type : field_type.clone(), inherited_value : value.clone(), depth : depth+1 });
+            }
+            continue;
+        }
+
+        // Terminal field
+        let value = value.and_then(|x| doc.resolve(&x));
+        let mut signature : Option<Signature> = None;
+        if field_type.as_deref() == Some("Sig"){
+            if let Some(sig_dict @ PdfVar::Dictionary(_)) = &value{
+                signature = Some(Signature::from(doc, sig_dict));
+            }
+        }
+        let value = value.and_then(|x| get_value_text(doc, &x));
+        fields.push(FormField { name, field_type, value, signature });
+    }
+    fields
+}
+
+/// Returns the text of a field value, a string, a name or an array of strings
+fn get_value_text(doc : &mut Document, value : &PdfVar) -> Option<String>{
+    match value {
+        PdfVar::StringLiteral(_) => value.get_str(),
+        PdfVar::Name(name) => Some(name.to_string()),
+        PdfVar::Array(options) => {
+            let options : Vec<String> = options.iter()
+                .filter_map(|x| doc.resolve(x))
+                .filter_map(|x| x.get_str())
+                .collect();
+            (!options.is_empty()).then(|| options.join(", "))
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,142 @@
+use std::{collections::HashMap, fmt};
+
+use crate::pdf_object::PdfVar;
+
+// Keys of the Info dictionary with their own field in DocumentInfo
+const STANDARD_KEYS : [&str; 9] = ["Title", "Author", "Subject", "Keywords", "Creator", "Producer", "CreationDate", "ModDate", "Trapped"];
+
+/// The documents Info dictionary
+#[derive(Debug, Clone, Default)]
+pub struct DocumentInfo {
+    pub title : Option<String>,
+    pub author : Option<String>,
+    pub subject : Option<String>,
+    pub keywords : Option<String>,
+    pub creator : Option<String>, // Application that created the original document
+    pub producer : Option<String>, // Application that converted it to pdf
+    pub creation_date : Option<PdfDate>,
+    pub mod_date : Option<PdfDate>,
+    pub trapped : Option<String>, // True, False or Unknown
+    pub custom : HashMap<String, String>, // Other keys with text values
+}
+
+/// A date, D:YYYYMMDDHHmmSSOHH'mm'
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PdfDate {
+    pub year : i32,
+    pub month : u8,
+    pub day : u8,
+    pub hour : u8,
+    pub minute : u8,
+    pub second : u8,
+    pub utc_offset : Option<i32>, // Minutes east of UTC, None if the time zone is unknown
+}
+
+impl DocumentInfo {
+    /// Reads the values of an Info dictionary, indirect values have to be resolved already
+    pub(crate) fn from(info_dict : &HashMap<String, PdfVar>) -> Self{
+        let text = |key : &str| info_dict.get(key).and_then(get_text);
+        let date = |key : &str| text(key).and_then(|x| PdfDate::parse(&x));
+
+        let mut custom : HashMap<String, String> = HashMap::new();
+        for (key, value) in info_dict{
+            if STANDARD_KEYS.contains(&key.as_str()){
+                continue;
+            }
+            if let Some(value) = get_text(value){
+                custom.insert(key.to_string(), value);
+            }
+        }
+
+        // Trapped is a name, older files use a boolean
+        let trapped = match info_dict.get("Trapped") {
+            Some(PdfVar::Name(name)) => Some(name.to_string()),
+            Some(PdfVar::Boolean(true)) => Some("True".to_string()),
+            Some(PdfVar::Boolean(false)) => Some("False".to_string()),
+            _ => None,
+        };
+
+        DocumentInfo {
+            title : text("Title"),
+            author : text("Author"),
+            subject : text("Subject"),
+            keywords : text("Keywords"),
+            creator : text("Creator"),
+            producer : text("Producer"),
+            creation_date : date("CreationDate"),
+            mod_date : date("ModDate"),
+            trapped,
+            custom,
+        }
+    }
+}
+
+impl PdfDate {
+    /// Parses a pdf date. Everything after the year is optional, and the D: prefix is often missing
+    pub fn parse(date : &str) -> Option<Self>{
+        let date = date.trim();
+        let date = date.strip_prefix("D:").unwrap_or(date);
+        let bytes = date.as_bytes();
+        let mut ix = 0;
+
+        // Reads a number with a given number of digits, None if the field is missing
+        let mut read_field = |digits : usize| -> Option<u32>{
+            let field = bytes.get(ix..ix+digits)?;
+            if !field.iter().all(|x| x.is_ascii_digit()){
+                return None;
+            }
+            ix += digits;
+            Some(field.iter().fold(0, |acc, x| acc*10 + (x - b'0') as u32))
+        };
+
+        let year = read_field(4)? as i32;
+        let month = read_field(2).unwrap_or(1) as u8;
+        let day = read_field(2).unwrap_or(1) as u8;
+        let hour = read_field(2).unwrap_or(0) as u8;
+        let minute = read_field(2).unwrap_or(0) as u8;
+        let second = read_field(2).unwrap_or(0) as u8;
+
+        if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || minute > 59 || second > 59{
+            return None;
+        }
+
+        // Time zone, Z or +HH'mm' or -HH'mm'
+        let utc_offset = match bytes.get(ix) {
+            Some(b'Z') => Some(0),
+            Some(sign @ (b'+' | b'-')) => {
+                let sign : i32 = if *sign == b'-' { -1 } else { 1 };
+                let tz = date[ix+1..].replace('\'', "");
+                let tz_hours = tz.get(0..2).and_then(|x| x.parse::<i32>().ok());
+                let tz_minutes = tz.get(2..4).and_then(|x| x.parse::<i32>().ok()).unwrap_or(0);
+                tz_hours.filter(|x| *x <= 23 && tz_minutes <= 59).map(|x| sign * (x*60 + tz_minutes))
+            }
+            _ => None,
+        };
+
+        Some(PdfDate { year, month, day, hour, minute, second, utc_offset })
+    }
+}
+
+impl fmt::Display for PdfDate {
+    /// Formats the date as ISO 8601
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result{
+        write!(f, "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}", self.year, self.month, self.day, self.hour, self.minute, self.second)?;
+        match self.utc_offset {
+            Some(0) => write!(f, "Z"),
+            Some(offset) => {
+                let sign = if offset < 0 { '-' } else { '+' };
+                write!(f, "{}{:02}:{:02}", sign, offset.abs() / 60, offset.abs() % 60)
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+/// Returns the text of a string or a name
+fn get_text(value : &PdfVar) -> Option<String>{
+    match value {
+        PdfVar::StringLiteral(_) => value.get_str(),
+        PdfVar::Name(name) => Some(name.to_string()),
+        _ => None,
+    }
+}
@@ -2,16 +2,32 @@
 
 use document::{read_one_pdf, Document, PdfError};
 
+pub use pdf_object::PdfVar;
+
+pub mod annotation;
 mod decoding;
 pub mod document;
+pub mod embedded;
 mod encoding;
+pub mod form;
+pub mod info;
+pub mod outline;
+pub mod page;
+mod page_labels;
 mod pdf_object;
+mod security;
 mod text_parser;
+pub mod tree;
+pub mod xmp;
 
 pub fn load_pdf_doc(filepath : &str) -> Result<Document, PdfError> {
     read_one_pdf(filepath)
 }
 
+pub fn load_pdf_bytes(doc_u8 : Vec<u8>) -> Result<Document, PdfError> {
+    Document::from_bytes(doc_u8)
+}
+
 fn print_raw(doc_u8 : &Vec<u8>, ix : usize, size : usize){
     println!("\nRAW");
     for i in ix..ix+size{
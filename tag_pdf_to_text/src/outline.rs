@@ -0,0 +1,126 @@
+use std::collections::HashSet;
+
+use crate::document::Document;
+use crate::pdf_object::PdfVar;
+use crate::tree::find_in_name_tree;
+
+// Max number of named destinations and /D dictionaries followed to reach a page
+const MAX_DEST_LOOKUPS : usize = 8;
+
+/// A bookmark in the documents outline
+#[derive(Debug, Clone)]
+pub struct OutlineItem {
+    pub title : String,
+    pub level : usize, // 0 for top level bookmarks
+    pub page_index : Option<usize>, // Target page, starting at 0
+}
+
+/// Reads the outline tree of the catalog, the items are returned in reading order
+pub(crate) fn get_outline(doc : &mut Document) -> Vec<OutlineItem>{
+    let mut items : Vec<OutlineItem> = Vec::new();
+    let Some(catalog) = doc.get_catalog() else{
+        return items;
+    };
+    let Some(outlines) = catalog.get_dict_value("Outlines").and_then(|x| doc.resolve(x)) else{
+        return items;
+    };
+
+    // Stack of (item id, generation, level). The next sibling is pushed before the first child, so children are read first
+    let mut stack : Vec<(usize, usize, usize)> = Vec::new();
+    let mut visited : HashSet<usize> = HashSet::new();
+    if let Some((first_id, first_gen)) = outlines.get_dict_value("First").and_then(|x| x.get_object_ref()){
+        stack.push((first_id, first_gen, 0));
+    }
+
+    while let Some((item_id, generation, level)) = stack.pop(){
+        // Each item is read once, a /Next or /First pointing back at an earlier item is ignored
+        if level >= doc.limits.max_depth || !visited.insert(item_id){
+            continue;
+        }
+        let Some(item) = doc.get_object(item_id, generation) else{
+            continue;
+        };
+
+        let title = item.get_dict_value("Title")
+            .and_then(|x| doc.resolve(x))
+            .and_then(|x| x.get_str())
+            .unwrap_or_default();
+        let page_index = get_link_target(doc, &item);
+        items.push(OutlineItem { title, level, page_index });
+
+        if let Some((next_id, next_gen)) = item.get_dict_value("Next").and_then(|x| x.get_object_ref()){
+            stack.push((next_id, next_gen, level));
+        }
+        if let Some((first_id, first_gen)) = item.get_dict_value("First").and_then(|x| x.get_object_ref()){
+            stack.push((first_id, first_gen, level+1));
+        }
+    }
+    items
+}
+
+/// Returns the target page of a bookmark or a link, from its /Dest or its /A GoTo action
+pub(crate) fn get_link_target(doc : &mut Document, obj : &PdfVar) -> Option<usize>{
+    if let Some(dest) = obj.get_dict_value("Dest"){
+        return resolve_destination(doc, dest, 0);
+    }
+    let action = doc.resolve(obj.get_dict_value("A")?)?;
+    if action.get_dict_value("S")?.get_name()? != "GoTo"{
+        return None;
+    }
+    resolve_destination(doc, action.get_dict_value("D")?, 0)
+}
+
+/// Returns the page index of a destination. Either an array [page /XYZ left top zoom],
+/// a named destination, or a dictionary with the destination in /D
+fn resolve_destination(doc : &mut Document, dest : &PdfVar, lookups : usize) -> Option<usize>{
+    if lookups > MAX_DEST_LOOKUPS{
+        return None;
+    }
+    let dest = doc.resolve(dest)?;
+    match &dest {
+        PdfVar::Array(array) => {
+            match array.first()? {
+                PdfVar::IndirectObject(page_id, _) => doc.get_page_index(*page_id),
+                // Some writers use the page number instead of the page object
+                PdfVar::Integer { value, signed: false } => {
+                    let page_index = *value as usize;
+                    (page_index < doc.page_count()).then_some(page_index)
+                }
+                _ => None,
+            }
+        }
+        PdfVar::Name(name) => {
+            let key : Vec<u32> = name.bytes().map(|x| x as u32).collect();
+            let named_dest = get_named_destination(doc, &key)?;
+            resolve_destination(doc, &named_dest, lookups+1)
+        }
+        PdfVar::StringLiteral(key) => {
+            let named_dest = get_named_destination(doc, key)?;
+            resolve_destination(doc, &named_dest, lookups+1)
+        }
+        PdfVar::Dictionary(_) => {
+            resolve_destination(doc, dest.get_dict_value("D")?, lookups+1)
+        }
+        _ => None,
+    }
+}
+
+/// Looks up a named destination, in the /Dests name tree of the /Names dictionary,
+/// or in the older /Dests dictionary of the catalog
+fn get_named_destination(doc : &mut Document, key : &Vec<u32>) -> Option<PdfVar>{
+    let catalog = doc.get_catalog()?;
+
+    // Name tree, PDF 1.2 and later
+    if let Some(names) = catalog.get_dict_value("Names").and_then(|x| doc.resolve(x)){
+        if let Some(dests) = names.get_dict_value("Dests"){
+            if let Some(dest) = find_in_name_tree(doc, dests, key){
+                return Some(dest);
+            }
+        }
+    }
+
+    // Dictionary of names, PDF 1.1
+    let dests = doc.resolve(catalog.get_dict_value("Dests")?)?;
+    let name : String = key.iter().filter_map(|x| char::from_u32(*x)).collect();
+    dests.get_dict_value(&name).cloned()
+}
@@ -0,0 +1,146 @@
+use crate::annotation::{get_annotations, get_notes, has_text_markup, Annotation, Note};
+use crate::document::Document;
+use crate::pdf_object::PdfVar;
+use crate::text_parser::{get_page_resources, read_objects_text, Text, TextRun};
+
+// Keys that a page inherits from its ancestors in the page tree
+const INHERITABLE : [&str; 4] = ["Resources", "MediaBox", "CropBox", "Rotate"];
+
+/// A page in the document, with inherited attributes resolved
+#[derive(Debug, Clone)]
+pub struct Page {
+    pub id : usize,
+    pub index : usize,
+    pub media_box : Option<[f64; 4]>,
+    pub crop_box : Option<[f64; 4]>,
+    pub rotate : i64,
+    pub(crate) object : PdfVar,
+    pub(crate) resources : Option<PdfVar>,
+}
+
+/// Iterator over the pages of a document
+pub struct Pages<'a> {
+    doc : &'a mut Document,
+    index : usize,
+}
+
+impl Page {
+    /// Reads a page object, and resolves the attributes it inherits from the page tree
+    pub(crate) fn from(doc : &mut Document, page_id : usize, index : usize) -> Option<Self>{
+        let object = doc.get_object_by_id(page_id)?;
+        let mut values : Vec<Option<PdfVar>> = vec![None; INHERITABLE.len()];
+
+        // Walk from the page up to the root of the page tree, the closest value is used.
+        // The number of ancestors is limited, a /Parent loop would never reach the root
+        let mut node = object.clone();
+        for _ in 0..doc.limits.max_depth{
+            for (i, key) in INHERITABLE.iter().enumerate(){
+                if values[i].is_some(){
+                    continue;
+                }
+                // A null value is the same as a missing key
+                if let Some(value) = node.get_dict_value(key){
+                    values[i] = doc.resolve(value).filter(|x| !matches!(x, PdfVar::Null));
+                }
+            }
+
+            // Go to parent
+            let Some((parent_id, parent_gen)) = node.get_dict_value("Parent").and_then(|x| x.get_object_ref()) else{
+                break;
+            };
+            let Some(parent) = doc.get_object(parent_id, parent_gen) else{
+                break;
+            };
+            node = parent;
+        }
+
+        let media_box = values[1].as_ref().and_then(get_rectangle);
+        let crop_box = values[2].as_ref().and_then(get_rectangle).or(media_box);
+
+        // Rotate has to be a multiple of 90
+        let mut rotate : i64 = 0;
+        if let Some(PdfVar::Integer { value, signed: _ }) = values[3]{
+            rotate = value.rem_euclid(360) / 90 * 90;
+        }
+
+        Some(Page{id : page_id, index, media_box, crop_box, rotate, object, resources : values[0].take()})
+    }
+
+    /// Returns the value of a key in the page dictionary
+    pub(crate) fn get_dict_value(&self, key : &str) -> Option<&PdfVar>{
+        self.object.get_dict_value(key)
+    }
+
+    /// Read Text Sections From Page
+    pub fn get_text(&self, doc : &mut Document) -> Option<Vec<Text>>{
+        // Get Page Fonts
+        let fonts = match &self.resources {
+            Some(resources) => get_page_resources(doc, resources),
+            None => get_page_resources(doc, &PdfVar::Null),
+        };
+
+        // Get ids of content
+        // If contents is non-existent page is empty
+        let Some(page_contents) = self.get_dict_value("Contents") else{
+            return None;
+        };
+        let Some(content_refs) = page_contents.get_object_ref_array() else{
+            return None;
+        };
+
+        read_objects_text(doc, content_refs, &fonts)
+    }
+
+    /// Returns the annotations of the page, such as links and comments
+    pub fn get_annotations(&self, doc : &mut Document) -> Vec<Annotation>{
+        match self.get_dict_value("Annots") {
+            Some(annots) => get_annotations(doc, annots),
+            None => Vec::new(),
+        }
+    }
+
+    /// Returns the comments and text markups on the page, with the text each markup covers
+    pub fn get_notes(&self, doc : &mut Document) -> Vec<Note>{
+        let annotations = self.get_annotations(doc);
+
+        // Page text is only needed to read the marked text
+        let mut runs : Vec<TextRun> = Vec::new();
+        if has_text_markup(&annotations){
+            for text in self.get_text(doc).unwrap_or_default(){
+                runs.extend(text.runs);
+            }
+        }
+        get_notes(annotations, self.index, &runs)
+    }
+}
+
+impl<'a> Pages<'a> {
+    pub(crate) fn new(doc : &'a mut Document) -> Self{
+        Pages { doc, index : 0 }
+    }
+}
+
+impl Iterator for Pages<'_> {
+    type Item = Page;
+
+    fn next(&mut self) -> Option<Page>{
+        let page = self.doc.get_page(self.index)?;
+        self.index += 1;
+        Some(page)
+    }
+}
+
+/// Reads a rectangle [llx lly urx ury]
+pub(crate) fn get_rectangle(rect_obj : &PdfVar) -> Option<[f64; 4]>{
+    let PdfVar::Array(array) = rect_obj else{
+        return None;
+    };
+    if array.len() != 4{
+        return None;
+    }
+    let mut rect : [f64; 4] = [0.0; 4];
+    for (i, value) in array.iter().enumerate(){
+        rect[i] = value.get_f64()?;
+    }
+    Some(rect)
+}
@@ -0,0 +1,103 @@
+use crate::document::Document;
+use crate::pdf_object::PdfVar;
+use crate::tree::NumberTree;
+
+// Largest start value of a range, /St is read from the file
+const MAX_START : i64 = 1_000_000;
+// Largest number written as roman numerals or letters, larger numbers are written as decimals
+const MAX_STYLED : i64 = 3999;
+
+/// A range of pages that share a numbering style, from a page label dictionary
+struct LabelRange {
+    first_page : usize, // Page index where the range starts
+    style : Option<String>, // D, R, r, A or a. None for labels with only a prefix
+    prefix : String,
+    start : i64, // Number of the first page in the range
+}
+
+/// Returns the printed label of every page, from the /PageLabels number tree of the catalog.
+/// None if the document has no page labels
+pub(crate) fn get_page_labels(doc : &mut Document) -> Option<Vec<String>>{
+    let catalog = doc.get_catalog()?;
+    let root = catalog.get_dict_value("PageLabels")?.clone();
+
+    // Read the ranges, keys are the index of the first page of each range
+    let mut ranges : Vec<LabelRange> = Vec::new();
+    let entries : Vec<(i64, PdfVar)> = NumberTree::new(doc, &root).collect();
+    for (key, label_dict) in entries{
+        if key < 0{
+            continue;
+        }
+        let style = label_dict.get_dict_value("S").and_then(|x| x.get_name());
+        let prefix = label_dict.get_dict_value("P")
+            .and_then(|x| doc.resolve(x))
+            .and_then(|x| x.get_str())
+            .unwrap_or_default();
+        let start = match label_dict.get_dict_value("St").and_then(|x| doc.resolve(x)) {
+            Some(PdfVar::Integer { value, signed: _ }) => value.clamp(1, MAX_START),
+            _ => 1,
+        };
+        ranges.push(LabelRange { first_page : key as usize, style, prefix, start });
+    }
+    if ranges.is_empty(){
+        return None;
+    }
+    ranges.sort_by_key(|x| x.first_page);
+
+    // Each page uses the last range that starts before it
+    let mut labels : Vec<String> = Vec::new();
+    for page_index in 0..doc.page_count(){
+        let Some(range) = ranges.iter().rev().find(|x| x.first_page <= page_index) else{
+            // The first range should start at page 0, pages before it get their page number
+            labels.push((page_index+1).to_string());
+            continue;
+        };
+        let Some(number) = i64::try_from(page_index - range.first_page).ok().and_then(|x| range.start.checked_add(x)) else{
+            labels.push((page_index+1).to_string());
+            continue;
+        };
+        labels.push(format!("{}{}", range.prefix, format_number(number, range.style.as_deref())));
+    }
+    Some(labels)
+}
+
+/// Formats a page number in a page label style
+fn format_number(number : i64, style : Option<&str>) -> String{
+    if number > MAX_STYLED && matches!(style, Some("R" | "r" | "A" | "a")){
+        return number.to_string();
+    }
+    match style {
+        Some("D") => number.to_string(),
+        Some("R") => to_roman(number).to_uppercase(),
+        Some("r") => to_roman(number),
+        Some("A") => to_letters(number).to_uppercase(),
+        Some("a") => to_letters(number),
+        _ => String::new(),
+    }
+}
+
+/// Lowercase roman numerals, i, ii, iii, iv, ...
+fn to_roman(mut number : i64) -> String{
+    const NUMERALS : [(i64, &str); 13] = [
+        (1000, "m"), (900, "cm"), (500, "d"), (400, "cd"), (100, "c"), (90, "xc"),
+        (50, "l"), (40, "xl"), (10, "x"), (9, "ix"), (5, "v"), (4, "iv"), (1, "i"),
+    ];
+    let mut roman = String::new();
+    for (value, numeral) in NUMERALS{
+        while number >= value{
+            roman.push_str(numeral);
+            number -= value;
+        }
+    }
+    roman
+}
+
+/// Lowercase letters, a to z, then aa to zz, aaa to zzz, ...
+fn to_letters(number : i64) -> String{
+    if number < 1{
+        return String::new();
+    }
+    let letter = (b'a' + ((number-1) % 26) as u8) as char;
+    let count = ((number-1) / 26 + 1) as usize;
+    letter.to_string().repeat(count)
+}
@@ -1,7 +1,7 @@
 use std::{collections::HashMap, vec};
 
-use crate::document::{Document, PdfError};
-use crate::decoding::{decode_flate, decode_pdfdoc, handle_decodeparms};
+use crate::document::{Document, ErrorKind, PdfError};
+use crate::decoding::{apply_filter, decode_pdfdoc};
 
 #[derive(Debug, Clone)]
 pub enum PdfVar {
@@ -14,9 +14,9 @@ pub enum PdfVar {
     Dictionary(std::collections::HashMap<String, PdfVar>),
     Null,
     Stream{start : usize, size : usize},
-    IndirectObject(usize),
+    IndirectObject(usize, usize), // (Id, Generation)
     Object{_id : usize, content : Vec<PdfVar>},
-    ObjectRef(usize),
+    ObjectRef(usize, usize), // (Id, Generation)
 }
 
 impl PdfVar {
@@ -57,6 +57,22 @@ impl PdfVar {
         return Some(output);
     }
 
+    /// Returns the (object id, generation) of an indirect object reference
+    pub(crate) fn get_object_ref(&self) -> Option<(usize, usize)>{
+        if let PdfVar::IndirectObject(obj_id, generation) = self{
+            return Some((*obj_id, *generation));
+        };
+        return None;
+    }
+
+    /// Returns the (object id, generation) of each reference in an array, or of a single reference
+    pub(crate) fn get_object_ref_array(&self) -> Option<Vec<(usize, usize)>>{
+        let PdfVar::Array(array) = self else{
+            return Some(vec![self.get_object_ref()?]);
+        };
+        array.iter().map(|x| x.get_object_ref()).collect()
+    }
+
     /// Get integer value from dictionary key
     pub fn get_dict_int(&self, key : &str) -> Option<usize> {
         let int_object = self.get_dict_value(key)?;
@@ -97,9 +113,15 @@ impl PdfVar {
         return None;
     }
 
+    /// Returns the text of a string value in a dictionary, the value can be an indirect object
+    pub(crate) fn get_dict_str(&self, key : &str, doc : &mut Document) -> Option<String>{
+        let value = doc.resolve(self.get_dict_value(key)?)?;
+        value.get_str()
+    }
+
     /// Get index of indirect object. If called with an integer, that value is returned
     pub fn get_indirect_obj_index(&self) -> Option<usize>{
-        if let PdfVar::IndirectObject(value) = self{
+        if let PdfVar::IndirectObject(value, _) = self{
             return Some(*value);
         };
         if let PdfVar::Integer { value, signed: _ } = self {
@@ -116,9 +138,9 @@ impl PdfVar {
             }
             return Some(*value as usize);
         };
-        if let PdfVar::IndirectObject(obj_id) = self {
+        if let PdfVar::IndirectObject(obj_id, generation) = self {
             let doc_ix = doc.it;
-            let Some(object) = doc.get_object_by_id(*obj_id) else {
+            let Some(object) = doc.get_object(*obj_id, *generation) else {
                 doc.it = doc_ix;
                 return None;
             };
@@ -147,63 +169,74 @@ impl PdfVar {
         return None;
     }
 
-    /// Returns a decoded stream
+    /// Returns a decoded stream. Streams over the resource limits are recorded as warnings
     pub fn get_decoded_stream(&self, doc : &mut Document) -> Option<Vec<u8>>{
+        match self.decode_stream(doc) {
+            Ok(decoded) => Some(decoded),
+            Err(e) => {
+                if e.kind == ErrorKind::LimitError{
+                    doc.warn(e);
+                }
+                None
+            }
+        }
+    }
+
+    /// Returns a decoded stream, or the reason it could not be decoded
+    pub(crate) fn decode_stream(&self, doc : &mut Document) -> Result<Vec<u8>, PdfError>{
         // Self must be an object
         let PdfVar::Object { _id, content } = &self else{
-            return None;
+            return Err(PdfError::new(ErrorKind::StreamError, "decode stream"));
         };
         
         // Stream object should be on index 2
         let Some(stream_obj) = content.get(2) else{
-            return None;
+            return Err(PdfError::new(ErrorKind::StreamError, "decode stream").in_object(*_id));
         };
         
         // Fetch stream information
         let PdfVar::Stream { start, size } = stream_obj else{
-            return None;
+            return Err(PdfError::new(ErrorKind::StreamError, "decode stream").in_object(*_id));
         };
 
-        // Get filter type
+        // Get filter types, a name or an array of names
         let mut filters : Vec<String> = Vec::new();
-        if let Some(filter_obj) = self.get_dict_value("Filter") {
-            if let Some(fname) = filter_obj.get_names(){
-                filters = fname;
-            }
-        };
-
-        // Match filter type
-        let mut decoded = doc.data[*start..*start+*size].to_vec();
-        for filter in filters{
-            match filter.as_str() {
-                "FlateDecode" =>{
-                    let Ok(_) = decode_flate(&mut decoded) else {
-                        return None;
+        match self.get_dict_value("Filter").and_then(|x| doc.resolve(x)) {
+            Some(PdfVar::Name(name)) => filters.push(name),
+            Some(PdfVar::Array(names)) => {
+                for name_obj in names.iter(){
+                    let Some(name) = doc.resolve(name_obj).and_then(|x| x.get_name()) else{
+                        return Err(PdfError::new(ErrorKind::DecodeError, "read stream filters").at(*start).in_object(*_id));
                     };
-                }
-                "LZWDecode" => {
-                    // Not implemented
-                    return None;
-                }
-                "" => {
-                    decoded = doc.data[*start..*start+*size].to_vec();
-                }
-                _ => {
-                    // Unknown filter type
-                    return None;
+                    filters.push(name);
                 }
             }
+            _ => {}
         }
 
-        // Handle DecodeParms
-        if let Some(decodeparms_obj) = self.get_dict_value("DecodeParms"){
-            if let Ok(vec) = handle_decodeparms(decoded, decodeparms_obj, doc) {
-                return Some(vec);
+        // Get the DecodeParms of each filter. An array has one entry per filter, with null for filters without parameters.
+        // A single dictionary belongs to a single filter, it is paired with the first filter like an array of one entry
+        let mut decodeparms : Vec<Option<PdfVar>> = Vec::new();
+        match self.get_dict_value("DecodeParms").and_then(|x| doc.resolve(x)) {
+            Some(PdfVar::Array(parms)) => {
+                for parms_obj in parms.iter(){
+                    decodeparms.push(doc.resolve(parms_obj).filter(|x| matches!(x, PdfVar::Dictionary(_))));
+                }
             }
-            return None;
-        };
+            Some(parms @ PdfVar::Dictionary(_)) => {
+                decodeparms.push(Some(parms));
+            }
+            _ => {}
+        }
+
+        // Apply the filters in order
+        let mut decoded = doc.data[*start..*start+*size].to_vec();
+        for (i, filter) in filters.iter().enumerate(){
+            let parms = decodeparms.get(i).and_then(|x| x.as_ref());
+            decoded = apply_filter(decoded, filter, parms, doc).map_err(|e| e.at(*start).in_object(*_id))?;
+        }
         
-        return Some(decoded);
+        return Ok(decoded);
     }
 
     /// Parses a document object starting from index
@@ -216,9 +249,12 @@ impl PdfVar {
         // Loop until endobj-tag is found
         loop {
             if doc.it >= doc.size(){
-                return Err(PdfError::ObjectError);
+                return Err(doc.error(ErrorKind::ObjectError, "parse object"));
             }
-            parse_object(doc, &mut obj_stack)?;
+            parse_object(doc, &mut obj_stack).map_err(|e| match obj_stack.first() {
+                Some(PdfVar::ObjectRef(obj_id, _)) => e.in_object(*obj_id),
+                _ => e,
+            })?;
             doc.skip_whitespace();
             
             if cmp_u8(&doc.data, doc.it, b"endobj"){
@@ -227,13 +263,13 @@ impl PdfVar {
         }
 
         if obj_stack.len() < 2{
-            return Err(PdfError::ObjectError);
+            return Err(doc.error(ErrorKind::ObjectError, "parse object"));
         }
         let Some(first_obj) = obj_stack.get(0) else{
-            return Err(PdfError::ObjectError);
+            return Err(doc.error(ErrorKind::ObjectError, "parse object"));
         };
-        let PdfVar::ObjectRef(obj_ref) = first_obj else{
-            return Err(PdfError::ObjectError);
+        let PdfVar::ObjectRef(obj_ref, _) = first_obj else{
+            return Err(doc.error(ErrorKind::ObjectError, "parse object"));
         };
         return Ok(PdfVar::Object{_id:obj_ref.clone(), content:obj_stack});
     }
@@ -244,20 +280,23 @@ impl PdfVar {
 /// Parses pdf types (int, real, array, indirect obj, strings, dictionaries, names, booleans, null, stream)
 pub fn parse_object(doc : &mut Document, stack : &mut Vec<PdfVar>) -> Result<(),PdfError>{
     doc.skip_whitespace();
-    match doc.byte(){
+    let Some(byte) = doc.byte() else{
+        return Err(doc.error(ErrorKind::DocumentError, "parse object"));
+    };
+    match byte{
         (48..58) | b'+' | b'-' | b'.' =>{
             // First char is numeric or +/-, can be float, int, (indirect obj)
             return obj_parse_numeric(doc, stack);
         }
         b'[' => {
             // First char [, array
-            return obj_parse_array(doc, stack);
+            return parse_nested(doc, stack, obj_parse_array);
         }
         b'<' => {
             // Hexstring or dictionary, depending on next char
-            if doc.data[doc.it+1] == b'<' {
+            if doc.data.get(doc.it+1) == Some(&b'<') {
                 // Dictionary
-                return obj_parse_dictionary(doc, stack);
+                return parse_nested(doc, stack, obj_parse_dictionary);
             }
             else{
                 // Hexstring
@@ -287,7 +326,18 @@ pub fn parse_object(doc : &mut Document, stack : &mut Vec<PdfVar>) -> Result<(),
         _ => {
         }
     }
-    Err(PdfError::UnmatchedChar)
+    Err(doc.error(ErrorKind::UnmatchedChar, "parse object"))
+}
+
+/// Parses an array or a dictionary, failing if they are nested deeper than the limit
+fn parse_nested(doc : &mut Document, stack : &mut Vec<PdfVar>, parse : fn(&mut Document, &mut Vec<PdfVar>) -> Result<(), PdfError>) -> Result<(), PdfError>{
+    if doc.nesting >= doc.limits.max_depth{
+        return Err(doc.error(ErrorKind::LimitError, "parse nested object"));
+    }
+    doc.nesting += 1;
+    let result = parse(doc, stack);
+    doc.nesting -= 1;
+    result
 }
 
 /// Parse array object
@@ -298,7 +348,7 @@ fn obj_parse_array(doc : &mut Document, stack : &mut Vec<PdfVar>) -> Result<(),
     let mut array_stack : Vec<PdfVar> = Vec::new();
     loop {
         doc.skip_whitespace();
-        if doc.byte() == b']'{
+        if doc.byte() == Some(b']'){
             doc.it += 1;
             break;
         }
@@ -321,7 +371,7 @@ fn obj_parse_const(doc : &mut Document, stack : &mut Vec<PdfVar>) -> Result<(),
         doc.it += 5;
         stack.push(PdfVar::Boolean(false));
     } else{
-        return Err(PdfError::UnmatchedChar);
+        return Err(doc.error(ErrorKind::UnmatchedChar, "parse constant"));
     }
     Ok(())
 }
@@ -347,13 +397,13 @@ fn obj_parse_dictionary(doc : &mut Document, stack : &mut Vec<PdfVar>) -> Result
     // Pop 2 items at a time, key and value
     while dict_stack.len() > 0{
         let Some(obj2) = dict_stack.pop() else{
-            return Err(PdfError::DictionaryError); 
+            return Err(doc.error(ErrorKind::DictionaryError, "parse dictionary")); 
         };
         let Some(obj1) = dict_stack.pop() else {
-            return Err(PdfError::DictionaryError); 
+            return Err(doc.error(ErrorKind::DictionaryError, "parse dictionary")); 
         };
         let PdfVar::Name(obj1_name) = obj1 else{
-            return Err(PdfError::DictionaryError);
+            return Err(doc.error(ErrorKind::DictionaryError, "parse dictionary"));
         };
         dict.insert(obj1_name, obj2);
     }
@@ -367,15 +417,15 @@ fn obj_parse_hex_string(doc : &mut Document, stack : &mut Vec<PdfVar>) -> Result
     doc.it += 1;
 
     if doc.it >= doc.size(){
-        return Err(PdfError::DocumentError);
+        return Err(doc.error(ErrorKind::DocumentError, "parse hex string"));
     }
     
-    while doc.byte().is_ascii_alphanumeric() {
+    while let Some(first) = doc.byte().filter(|x| x.is_ascii_alphanumeric()) {
         if doc.it + 1 >= doc.size(){
-            return Err(PdfError::DocumentError);
+            return Err(doc.error(ErrorKind::DocumentError, "parse hex string"));
         }
 
-        let mut chars : Vec<u8> = vec![doc.byte(), doc.data[doc.it+1]];
+        let mut chars : Vec<u8> = vec![first, doc.data[doc.it+1]];
 
         // If last char is not included, it is assumed to be 0
         if !chars[1].is_ascii_alphanumeric(){
@@ -388,11 +438,11 @@ fn obj_parse_hex_string(doc : &mut Document, stack : &mut Vec<PdfVar>) -> Result
         doc.it += 2;
     }
     if doc.it >= doc.size(){
-        return Err(PdfError::DocumentError);
+        return Err(doc.error(ErrorKind::DocumentError, "parse hex string"));
     }
     // Check for correct ending
-    if doc.byte() != b'>' {
-        return Err(PdfError::HexError);
+    if doc.byte() != Some(b'>') {
+        return Err(doc.error(ErrorKind::HexError, "parse hex string"));
     }
     doc.it += 1;
     stack.push(PdfVar::StringLiteral(hex_vector));
@@ -407,10 +457,11 @@ fn obj_parse_name(doc : &mut Document, stack : &mut Vec<PdfVar>) -> Result<(), P
     loop {
         // Within bounds
         if doc.it + 2 >= doc.size(){
-            return Err(PdfError::DocumentError);
+            return Err(doc.error(ErrorKind::DocumentError, "parse name"));
         }
 
-        if doc.byte() == b'#'{
+        let byte = doc.data[doc.it];
+        if byte == b'#'{
             // Convert to hex
             let nums = vec![doc.data[doc.it+1], doc.data[doc.it+2]];
             let hex = to_hex(&nums)?;
@@ -418,11 +469,11 @@ fn obj_parse_name(doc : &mut Document, stack : &mut Vec<PdfVar>) -> Result<(), P
             doc.it += 3;
         } else if is_delimiter(&doc.data, doc.it){
             break;
-        } else if (31..127).contains(&doc.byte()){
-            chars.push(doc.byte() as u32);
+        } else if (31..127).contains(&byte){
+            chars.push(byte as u32);
             doc.it += 1;
         } else{
-            return Err(PdfError::ObjectError);
+            return Err(doc.error(ErrorKind::ObjectError, "parse name"));
         }
     }
     let name : String = decode_pdfdoc(&chars);
@@ -432,25 +483,25 @@ fn obj_parse_name(doc : &mut Document, stack : &mut Vec<PdfVar>) -> Result<(), P
 
 /// Parses a numeric object
 pub(crate) fn obj_parse_numeric(doc : &mut Document, stack : &mut Vec<PdfVar>) -> Result<(), PdfError>{
-    let signed = doc.byte() == b'+' || doc.byte() == b'-';
+    let signed = matches!(doc.byte(), Some(b'+' | b'-'));
     let mut number_str = String::new();
     
-    while doc.byte().is_ascii_digit() || matches!(doc.data[doc.it], b'+' | b'-' | b'.') {
-        number_str.push(doc.byte() as char);
+    while let Some(byte @ (b'0'..=b'9' | b'+' | b'-' | b'.')) = doc.byte() {
+        number_str.push(byte as char);
         doc.it += 1;
 
         // Within bounds
         if doc.it >= doc.size(){
-            return Err(PdfError::DocumentError);
+            return Err(doc.error(ErrorKind::DocumentError, "parse number"));
         }
     }
     
     // Attempts to convert to int, if fails try float, if fail return None
     if let Ok(number_i64) = number_str.parse::<i64>(){
-        stack.push(PdfVar::Integer{value : number_i64 as i64, signed : signed});
+        stack.push(PdfVar::Integer{value : number_i64 as i64, signed});
     } else{
         let Ok(number_f64) = number_str.parse::<f64>() else {
-            return Err(PdfError::ObjectError);
+            return Err(doc.error(ErrorKind::ObjectError, "parse number"));
         };
         stack.push(PdfVar::Real(number_f64));
     }
@@ -460,44 +511,44 @@ pub(crate) fn obj_parse_numeric(doc : &mut Document, stack : &mut Vec<PdfVar>) -
 /// Parse indirec object (D D R) or object head (D D obj)
 fn obj_parse_object_ref(doc : &mut Document, stack : &mut Vec<PdfVar>) -> Result<(), PdfError>{
     let mut indirect_obj = false;
-    if doc.byte() == b'R' {
+    if doc.byte() == Some(b'R') {
         // Next char has to be a delimiter
         if !is_delimiter(&doc.data, doc.it+1){
-            return Err(PdfError::UnmatchedChar);
+            return Err(doc.error(ErrorKind::UnmatchedChar, "parse object reference"));
         }
         doc.it += 1;
         indirect_obj = true;
     } else if cmp_u8(&doc.data, doc.it, b"obj"){
         doc.it += 3;
     } else{
-        return Err(PdfError::ObjectRefError);
+        return Err(doc.error(ErrorKind::ObjectRefError, "parse object reference"));
     }
     
     // 2 previous integers gets popped from stack
     let Some(arg_2) = stack.pop() else{
-        return Err(PdfError::ObjectRefError);
+        return Err(doc.error(ErrorKind::ObjectRefError, "parse object reference"));
     };
     let Some(arg_1) = stack.pop() else{
-        return Err(PdfError::ObjectRefError);
+        return Err(doc.error(ErrorKind::ObjectRefError, "parse object reference"));
     };
 
     // Inspect arg_1, arg_2
     let PdfVar::Integer{ value : a1_value, signed : a1_signed} = arg_1 else {
-        return Err(PdfError::ObjectRefError);
+        return Err(doc.error(ErrorKind::ObjectRefError, "parse object reference"));
     };
-    let PdfVar::Integer{ value : _a2_value, signed : a2_signed} = arg_2 else {
-        return Err(PdfError::ObjectRefError);
+    let PdfVar::Integer{ value : a2_value, signed : a2_signed} = arg_2 else {
+        return Err(doc.error(ErrorKind::ObjectRefError, "parse object reference"));
     };
 
     if a1_signed || a2_signed{
-        return Err(PdfError::ObjectRefError);
+        return Err(doc.error(ErrorKind::ObjectRefError, "parse object reference"));
     }
 
     if indirect_obj {
-        stack.push(PdfVar::IndirectObject(a1_value as usize));
+        stack.push(PdfVar::IndirectObject(a1_value as usize, a2_value as usize));
     }
     else {
-        stack.push(PdfVar::ObjectRef(a1_value as usize));
+        stack.push(PdfVar::ObjectRef(a1_value as usize, a2_value as usize));
     }
     Ok(())
 }
@@ -505,34 +556,67 @@ fn obj_parse_object_ref(doc : &mut Document, stack : &mut Vec<PdfVar>) -> Result
 /// Parse an object stream
 fn obj_parse_stream(doc : &mut Document, stack : &mut Vec<PdfVar>) -> Result<(), PdfError>{
     if !cmp_u8(&doc.data, doc.it, b"stream"){
-        return Err(PdfError::UnmatchedChar);
+        return Err(doc.error(ErrorKind::UnmatchedChar, "parse stream"));
     }
 
     doc.it += 6;
-    doc.skip_whitespace();
+
+    // Stream data starts after the end of line (CRLF or LF), the data itself can start with whitespace
+    if cmp_u8(&doc.data, doc.it, b"\r\n"){
+        doc.it += 2;
+    } else if matches!(doc.data.get(doc.it), Some(10 | 13)){
+        doc.it += 1;
+    } else {
+        doc.skip_whitespace();
+    }
     
     let start:usize = doc.it;
     let Some(stream_dict_obj) = stack.last() else{
-        return Err(PdfError::StreamError);
-    };
-    let Some(length_obj) = stream_dict_obj.get_dict_value("Length") else{
-        return Err(PdfError::StreamError);
-    };
-    let Some(size) = length_obj.get_usize(doc) else{
-        return Err(PdfError::StreamError);
+        return Err(doc.error(ErrorKind::StreamError, "parse stream"));
     };
 
-    doc.it += size;
-    doc.skip_whitespace();
+    // Trust Length if endstream follows the data
+    let length = stream_dict_obj.get_dict_value("Length").and_then(|x| x.get_usize(doc));
+    if let Some(end) = length.and_then(|x| start.checked_add(x)).filter(|x| *x <= doc.size()){
+        doc.it = end;
+        doc.skip_whitespace();
+        if cmp_u8(&doc.data, doc.it, b"endstream"){
+            doc.it += 9;
+            stack.push(PdfVar::Stream{start,size:end-start});
+            return Ok(());
+        }
+    }
 
-    if !cmp_u8(&doc.data, doc.it, b"endstream"){
-        return Err(PdfError::StreamError);
+    // Length is missing or wrong, the data ends at the endstream keyword
+    let Some(size) = find_stream_size(&doc.data, start) else{
+        doc.it = start;
+        return Err(doc.error(ErrorKind::StreamError, "parse stream"));
+    };
+    let mut warning = PdfError::new(ErrorKind::StreamError, "recover stream length").at(start);
+    if let Some(PdfVar::ObjectRef(obj_id, _)) = stack.first(){
+        warning = warning.in_object(*obj_id);
     }
+    doc.warn(warning);
+
+    doc.it = start + size;
+    doc.skip_whitespace();
     doc.it += 9;
-    stack.push(PdfVar::Stream{start:start,size:size});
+    stack.push(PdfVar::Stream{start,size});
     Ok(())
 }
 
+/// Returns the size of stream data, from its start to the end of line before endstream
+fn find_stream_size(data : &Vec<u8>, start : usize) -> Option<usize>{
+    let end = start + data.get(start..)?.windows(9).position(|x| x == b"endstream")?;
+    if end >= start+2 && &data[end-2..end] == b"\r\n"{
+        return Some(end-2-start);
+    }
+    if end > start && matches!(data[end-1], 10 | 13){
+        return Some(end-1-start);
+    }
+    Some(end-start)
+}
+
 /// Parse an object string literal
 fn obj_parse_string_literal(doc : &mut Document, stack : &mut Vec<PdfVar>) -> Result<(), PdfError>{
     let mut parenthesis_depth = 1;
@@ -540,10 +624,10 @@ fn obj_parse_string_literal(doc : &mut Document, stack : &mut Vec<PdfVar>) -> Re
     let mut reading_err = false; // set to true when reading error occurs
     loop {
         doc.it += 1;
-        if doc.it >= doc.size(){
-            return Err(PdfError::ObjectError);
-        }
-        match doc.byte() {
+        let Some(byte) = doc.byte() else{
+            return Err(doc.error(ErrorKind::ObjectError, "parse string"));
+        };
+        match byte {
             b'\\' => {
                 doc.it += 1;
                 if reading_err {
@@ -557,7 +641,7 @@ fn obj_parse_string_literal(doc : &mut Document, stack : &mut Vec<PdfVar>) -> Re
             b'(' => {
                 parenthesis_depth += 1;
                 if !reading_err {
-                    literal.push(doc.byte() as u32);
+                    literal.push(byte as u32);
                 }
             }
             b')' => {
@@ -566,12 +650,12 @@ fn obj_parse_string_literal(doc : &mut Document, stack : &mut Vec<PdfVar>) -> Re
                     break;
                 }
                 if !reading_err {
-                    literal.push(doc.byte() as u32);
+                    literal.push(byte as u32);
                 }
             }
             _ => {
                 if !reading_err {
-                    literal.push(doc.byte() as u32);
+                    literal.push(byte as u32);
                 }
             }
         }
@@ -590,7 +674,7 @@ pub fn to_hex(nums : &[u8]) -> Result<u32,PdfError>{
             b'a'..=b'f' => num - 87,
             b'0'..=b'9' => num - 48,
             _ => {
-                return Err(PdfError::HexError);
+                return Err(PdfError::new(ErrorKind::HexError, "parse hex digits"));
             }
         };
         value = value * 16 + v1 as u32;
@@ -599,49 +683,50 @@ pub fn to_hex(nums : &[u8]) -> Result<u32,PdfError>{
     Ok(value)
 }
 
-/// Parse escape \\ chars
+/// Parse escape \\ chars, the raw byte values are kept since encrypted strings are binary data
 fn handle_escape(doc : &mut Document, literal : &mut Vec<u32>) -> Option<()>{
-    match doc.data[doc.it] {
+    let byte = doc.byte()?;
+    match byte {
         b'n' => {
-            literal.push(32);
+            literal.push(10);
         }
         b'r' => {
-            // We dont care, or do we
             literal.push(13);
         }
-        b'b' => {
-            literal.push(8);
-        }
         b't' => {
             literal.push(9);
         }
-        b'(' | b')' | b'\\' => {
-            literal.push(doc.data[doc.it] as u32);
+        b'b' => {
+            literal.push(8);
+        }
+        b'f' => {
+            literal.push(12);
         }
         13 | 10 => {
-            // Skip new line chars
-            while matches!(doc.data[doc.it], 10 | 13) {
+            // Line continuation, one end of line (CR, LF or CRLF) is skipped
+            if byte == 13 && doc.data.get(doc.it+1) == Some(&10){
                 doc.it += 1;
             }
-            doc.it -= 1;
         }
-        _ => {
+        b'0'..=b'7' => {
+            // Octal character code, up to 3 digits
             let mut num : u32 = 0;
             let mut numsize = 0;
-            for i in 0..3{
-                if (48..56).contains(&doc.data[doc.it+i]){
-                    num = num*8+(doc.data[doc.it+i] - 48) as u32;
-                    numsize += 1;
-                    continue;
+            while let Some(digit @ b'0'..=b'7') = doc.data.get(doc.it+numsize).copied(){
+                num = num*8+(digit - 48) as u32;
+                numsize += 1;
+                if numsize == 3{
+                    break;
                 }
-                break;
             }
-            if numsize == 0{
-                return None;
-            }
-            literal.push(num);
+            // High-order overflow is ignored
+            literal.push(num & 0xFF);
             doc.it += numsize-1;
         }
+        _ => {
+            // The backslash is ignored for other chars, this includes ( ) and \\
+            literal.push(byte as u32);
+        }
     }
     Some(())
 }
@@ -661,5 +746,6 @@ pub fn cmp_u8(vector : &Vec<u8>, index : usize, byte_array : &[u8]) -> bool{
 
 /// Determines if a char in a vector on index is a delimiter
 pub fn is_delimiter(vector : &Vec<u8>, index: usize) -> bool{
-    matches!(vector[index],0|10|12|13|32|40|41|60|62|91|93|123|125|47|37)
+    // The end of the data also ends a token
+    vector.get(index).is_none_or(|x| matches!(x,0|10|12|13|32|40|41|60|62|91|93|123|125|47|37))
 }
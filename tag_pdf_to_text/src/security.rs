@@ -0,0 +1,392 @@
+use aes::cipher::{block_padding::{NoPadding, Pkcs7}, BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+use md5::{Digest, Md5};
+use sha2::{Sha256, Sha384, Sha512};
+
+use crate::document::Document;
+use crate::pdf_object::PdfVar;
+
+type Aes128CbcDec = cbc::Decryptor<aes::Aes128>;
+type Aes128CbcEnc = cbc::Encryptor<aes::Aes128>;
+type Aes256CbcDec = cbc::Decryptor<aes::Aes256>;
+
+// Padding string used for passwords, revision 2-4 (p. 125)
+const PASSWORD_PAD : [u8; 32] = [
+    0x28, 0xBF, 0x4E, 0x5E, 0x4E, 0x75, 0x8A, 0x41, 0x64, 0x00, 0x4E, 0x56, 0xFF, 0xFA, 0x01, 0x08,
+    0x2E, 0x2E, 0x00, 0xB6, 0xD0, 0x68, 0x3E, 0x80, 0x2F, 0x0C, 0xA9, 0xFE, 0x64, 0x53, 0x69, 0x7A,
+];
+
+/// The encryption method used for strings or streams
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CryptMethod {
+    Identity,
+    Rc4,
+    Aes128,
+    Aes256,
+}
+
+/// The standard security handler, read from the Encrypt dictionary
+#[derive(Debug)]
+pub(crate) struct SecurityHandler {
+    revision : usize,
+    key_length : usize, // in bytes
+    owner : Vec<u8>,    // O
+    user : Vec<u8>,     // U
+    owner_key : Vec<u8>, // OE
+    user_key : Vec<u8>,  // UE
+    permissions : i32,  // P
+    encrypt_metadata : bool,
+    file_id : Vec<u8>,  // First element of the trailer ID
+    string_method : CryptMethod,
+    stream_method : CryptMethod,
+    key : Option<Vec<u8>>, // File encryption key, set when a password is accepted
+    pub(crate) encrypt_id : usize,
+    pub(crate) data_len : usize, // Objects appended after the file data are never encrypted
+}
+
+impl SecurityHandler {
+    /// Reads the Encrypt dictionary. Returns None if the security handler is not supported
+    pub(crate) fn from(doc : &mut Document, encrypt_id : usize, file_id : Vec<u8>) -> Option<Self>{
+        let encrypt_obj = doc.get_object_by_id(encrypt_id)?;
+
+        // Only the standard security handler is supported
+        if encrypt_obj.get_dict_value("Filter")?.get_name()? != "Standard"{
+            return None;
+        }
+
+        let version = encrypt_obj.get_dict_int("V").unwrap_or(0);
+        let revision = encrypt_obj.get_dict_int("R")?;
+        let key_bits = encrypt_obj.get_dict_int("Length").unwrap_or(40);
+
+        let mut handler = SecurityHandler{
+            revision,
+            key_length : (key_bits / 8).clamp(5, 16),
+            owner : get_bytes(&encrypt_obj, "O")?,
+            user : get_bytes(&encrypt_obj, "U")?,
+            owner_key : get_bytes(&encrypt_obj, "OE").unwrap_or_default(),
+            user_key : get_bytes(&encrypt_obj, "UE").unwrap_or_default(),
+            permissions : get_permissions(&encrypt_obj)?,
+            encrypt_metadata : !matches!(encrypt_obj.get_dict_value("EncryptMetadata"), Some(PdfVar::Boolean(false))),
+            file_id,
+            string_method : CryptMethod::Rc4,
+            stream_method : CryptMethod::Rc4,
+            key : None,
+            encrypt_id,
+            data_len : doc.file_size(),
+        };
+
+        match version {
+            1 | 2 => {
+                if revision == 2{
+                    handler.key_length = 5;
+                }
+            }
+            4 | 5 => {
+                // Crypt filters
+                handler.string_method = get_crypt_method(&encrypt_obj, "StrF")?;
+                handler.stream_method = get_crypt_method(&encrypt_obj, "StmF")?;
+                if version == 4 && encrypt_obj.get_dict_value("Length").is_none(){
+                    handler.key_length = 16;
+                }
+                if version == 5{
+                    handler.key_length = 32;
+                }
+            }
+            _ => {
+                return None;
+            }
+        }
+        Some(handler)
+    }
+
+    /// Returns true if a password has been accepted
+    pub(crate) fn is_unlocked(&self) -> bool{
+        self.key.is_some()
+    }
+
+    /// Returns true if the Metadata stream is encrypted
+    pub(crate) fn encrypts_metadata(&self) -> bool{
+        self.encrypt_metadata
+    }
+
+    /// Tries a password, as user password and then as owner password. Returns true if accepted
+    pub(crate) fn authenticate(&mut self, password : &[u8]) -> bool{
+        let key = if self.revision >= 5 {
+            self.authenticate_aes256(password)
+        } else {
+            self.authenticate_user(password).or_else(|| self.authenticate_owner(password))
+        };
+        if key.is_some(){
+            self.key = key;
+            return true;
+        }
+        false
+    }
+
+    /// Decrypts a string of an object
+    pub(crate) fn decrypt_string(&self, obj_id : usize, gen : usize, data : &[u8]) -> Vec<u8>{
+        self.decrypt(self.string_method, obj_id, gen, data)
+    }
+
+    /// Decrypts a stream of an object
+    pub(crate) fn decrypt_stream(&self, obj_id : usize, gen : usize, data : &[u8]) -> Vec<u8>{
+        self.decrypt(self.stream_method, obj_id, gen, data)
+    }
+
+    /// Decrypts data, using the key for the given object
+    fn decrypt(&self, method : CryptMethod, obj_id : usize, gen : usize, data : &[u8]) -> Vec<u8>{
+        let Some(key) = &self.key else{
+            return data.to_vec();
+        };
+        match method {
+            CryptMethod::Identity => data.to_vec(),
+            CryptMethod::Rc4 => rc4(&object_key(key, obj_id, gen, false), data),
+            CryptMethod::Aes128 => aes_decrypt(&object_key(key, obj_id, gen, true), data),
+            CryptMethod::Aes256 => aes_decrypt(key, data),
+        }
+    }
+
+    /// Computes the file key from a password, revision 2-4 (Algorithm 2)
+    fn compute_key(&self, password : &[u8]) -> Vec<u8>{
+        let mut hasher = Md5::new();
+        hasher.update(pad_password(password));
+        hasher.update(&self.owner);
+        hasher.update(self.permissions.to_le_bytes());
+        hasher.update(&self.file_id);
+        if self.revision >= 4 && !self.encrypt_metadata{
+            hasher.update([0xFF, 0xFF, 0xFF, 0xFF]);
+        }
+        let mut hash = hasher.finalize().to_vec();
+
+        if self.revision >= 3{
+            for _ in 0..50{
+                hash = Md5::digest(&hash[..self.key_length]).to_vec();
+            }
+        }
+        hash.truncate(self.key_length);
+        hash
+    }
+
+    /// Checks the user password, returns the file key if correct (Algorithm 6)
+    fn authenticate_user(&self, password : &[u8]) -> Option<Vec<u8>>{
+        let key = self.compute_key(password);
+
+        if self.revision == 2{
+            // Algorithm 4
+            if rc4(&key, &PASSWORD_PAD) == self.user{
+                return Some(key);
+            }
+            return None;
+        }
+
+        // Algorithm 5
+        let mut hasher = Md5::new();
+        hasher.update(PASSWORD_PAD);
+        hasher.update(&self.file_id);
+        let mut hash = rc4(&key, &hasher.finalize());
+        for i in 1..20u8{
+            let round_key : Vec<u8> = key.iter().map(|x| x ^ i).collect();
+            hash = rc4(&round_key, &hash);
+        }
+        if self.user.len() >= 16 && hash[..16] == self.user[..16]{
+            return Some(key);
+        }
+        None
+    }
+
+    /// Checks the owner password, by recovering the user password from O (Algorithm 7)
+    fn authenticate_owner(&self, password : &[u8]) -> Option<Vec<u8>>{
+        let mut hash = Md5::digest(pad_password(password)).to_vec();
+        if self.revision >= 3{
+            for _ in 0..50{
+                hash = Md5::digest(&hash).to_vec();
+            }
+        }
+        hash.truncate(self.key_length);
+
+        let mut user_password = self.owner.clone();
+        if self.revision == 2{
+            user_password = rc4(&hash, &user_password);
+        } else {
+            for i in (0..20u8).rev(){
+                let round_key : Vec<u8> = hash.iter().map(|x| x ^ i).collect();
+                user_password = rc4(&round_key, &user_password);
+            }
+        }
+        self.authenticate_user(&user_password)
+    }
+
+    /// Checks the password as user and owner password, revision 5-6 (Algorithm 2.A)
+    fn authenticate_aes256(&self, password : &[u8]) -> Option<Vec<u8>>{
+        if self.user.len() < 48 || self.owner.len() < 48{
+            return None;
+        }
+        let password = &password[..password.len().min(127)];
+
+        // Owner password, hashed together with U
+        let user_data = &self.user[..48];
+        if self.hash_aes256(password, &self.owner[32..40], user_data) == self.owner[..32]{
+            let key = self.hash_aes256(password, &self.owner[40..48], user_data);
+            return aes256_key(&key, &self.owner_key);
+        }
+
+        // User password
+        if self.hash_aes256(password, &self.user[32..40], &[]) == self.user[..32]{
+            let key = self.hash_aes256(password, &self.user[40..48], &[]);
+            return aes256_key(&key, &self.user_key);
+        }
+        None
+    }
+
+    /// Password hash for revision 5 (SHA-256) and revision 6 (Algorithm 2.B)
+    fn hash_aes256(&self, password : &[u8], salt : &[u8], user_data : &[u8]) -> Vec<u8>{
+        let mut hasher = Sha256::new();
+        hasher.update(password);
+        hasher.update(salt);
+        hasher.update(user_data);
+        let mut k = hasher.finalize().to_vec();
+
+        if self.revision == 5{
+            return k;
+        }
+
+        let mut round : usize = 0;
+        loop {
+            // K1 = password + K + user data, repeated 64 times
+            let mut k1 : Vec<u8> = Vec::new();
+            for _ in 0..64{
+                k1.extend_from_slice(password);
+                k1.extend_from_slice(&k);
+                k1.extend_from_slice(user_data);
+            }
+            let e = Aes128CbcEnc::new_from_slices(&k[..16], &k[16..32])
+                .map(|x| x.encrypt_padded_vec_mut::<NoPadding>(&k1))
+                .unwrap_or_default();
+            if e.len() < 16{
+                return k;
+            }
+
+            // The first 16 bytes as a number, modulo 3, decides the hash function
+            let modulo : usize = e[..16].iter().map(|x| *x as usize).sum::<usize>() % 3;
+            k = match modulo {
+                0 => Sha256::digest(&e).to_vec(),
+                1 => Sha384::digest(&e).to_vec(),
+                _ => Sha512::digest(&e).to_vec(),
+            };
+
+            // At least 64 rounds, then until the last byte of E is small enough
+            round += 1;
+            if round >= 64 && (e[e.len()-1] as usize) + 32 <= round{
+                break;
+            }
+        }
+        k.truncate(32);
+        k
+    }
+}
+
+/// Pads or truncates a password to 32 bytes
+fn pad_password(password : &[u8]) -> Vec<u8>{
+    let mut padded : Vec<u8> = password.iter().take(32).copied().collect();
+    padded.extend_from_slice(&PASSWORD_PAD[..32-padded.len()]);
+    padded
+}
+
+/// Computes the key for one object (Algorithm 1)
+fn object_key(key : &[u8], obj_id : usize, gen : usize, aes : bool) -> Vec<u8>{
+    let mut hasher = Md5::new();
+    hasher.update(key);
+    hasher.update(&(obj_id as u32).to_le_bytes()[..3]);
+    hasher.update(&(gen as u32).to_le_bytes()[..2]);
+    if aes{
+        hasher.update(b"sAlT");
+    }
+    let mut hash = hasher.finalize().to_vec();
+    hash.truncate((key.len() + 5).min(16));
+    hash
+}
+
+/// Decrypts the file key from OE or UE, using the intermediate key
+fn aes256_key(intermediate : &[u8], encrypted_key : &[u8]) -> Option<Vec<u8>>{
+    if encrypted_key.len() < 32{
+        return None;
+    }
+    let decryptor = Aes256CbcDec::new_from_slices(intermediate, &[0u8; 16]).ok()?;
+    decryptor.decrypt_padded_vec_mut::<NoPadding>(&encrypted_key[..32]).ok()
+}
+
+/// RC4, encryption and decryption are the same operation
+fn rc4(key : &[u8], data : &[u8]) -> Vec<u8>{
+    let mut state : Vec<u8> = (0..=255).collect();
+    let mut j : usize = 0;
+    for i in 0..256{
+        j = (j + state[i] as usize + key[i % key.len()] as usize) % 256;
+        state.swap(i, j);
+    }
+
+    let mut output : Vec<u8> = Vec::with_capacity(data.len());
+    let (mut i, mut j) = (0usize, 0usize);
+    for byte in data{
+        i = (i + 1) % 256;
+        j = (j + state[i] as usize) % 256;
+        state.swap(i, j);
+        output.push(byte ^ state[(state[i] as usize + state[j] as usize) % 256]);
+    }
+    output
+}
+
+/// AES-CBC decryption, the first 16 bytes are the initialization vector
+fn aes_decrypt(key : &[u8], data : &[u8]) -> Vec<u8>{
+    if data.len() < 32 || !data.len().is_multiple_of(16){
+        return Vec::new();
+    }
+    let (iv, encrypted) = data.split_at(16);
+    let decrypted = match key.len() {
+        16 => Aes128CbcDec::new_from_slices(key, iv).ok().map(|x| x.decrypt_padded_vec_mut::<Pkcs7>(encrypted)),
+        _ => Aes256CbcDec::new_from_slices(key, iv).ok().map(|x| x.decrypt_padded_vec_mut::<Pkcs7>(encrypted)),
+    };
+    match decrypted {
+        Some(Ok(x)) => x,
+        _ => Vec::new(),
+    }
+}
+
+/// Returns the bytes of a string in the Encrypt dictionary
+fn get_bytes(encrypt_obj : &PdfVar, key : &str) -> Option<Vec<u8>>{
+    let PdfVar::StringLiteral(chars) = encrypt_obj.get_dict_value(key)? else{
+        return None;
+    };
+    Some(chars.iter().map(|x| *x as u8).collect())
+}
+
+/// Returns the P value, a signed 32 bit integer
+fn get_permissions(encrypt_obj : &PdfVar) -> Option<i32>{
+    let PdfVar::Integer { value, signed: _ } = encrypt_obj.get_dict_value("P")? else{
+        return None;
+    };
+    Some(*value as i32)
+}
+
+/// Reads the crypt filter method for StrF or StmF
+fn get_crypt_method(encrypt_obj : &PdfVar, key : &str) -> Option<CryptMethod>{
+    let filter_name = match encrypt_obj.get_dict_value(key) {
+        Some(x) => x.get_name()?,
+        None => "Identity".to_string(),
+    };
+    if filter_name == "Identity"{
+        return Some(CryptMethod::Identity);
+    }
+
+    // Look up the filter in the CF dictionary
+    let filter = encrypt_obj.get_dict_value("CF")?.get_dict_value(&filter_name)?;
+    let method = match filter.get_dict_value("CFM") {
+        Some(x) => x.get_name()?,
+        None => "None".to_string(),
+    };
+    match method.as_str() {
+        "None" => Some(CryptMethod::Identity),
+        "V2" => Some(CryptMethod::Rc4),
+        "AESV2" => Some(CryptMethod::Aes128),
+        "AESV3" => Some(CryptMethod::Aes256),
+        _ => None,
+    }
+}
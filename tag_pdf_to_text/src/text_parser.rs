@@ -2,7 +2,7 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use std::vec;
 use crate::pdf_object::{cmp_u8, is_delimiter, obj_parse_numeric, parse_object, to_hex, PdfVar};
-use crate::document::{Document, PdfError};
+use crate::document::{Document, ErrorKind};
 use crate::decoding::decode_pdfdoc_char;
 use crate::print_raw;
 
@@ -12,9 +12,21 @@ pub struct Text{
     pub scaled_font_size : f64,
     pub chars : String,
     pub avg_font_size : f64,
+    pub runs : Vec<TextRun>, // The strings of the section, with their positions
     font : String,
 }
 
+/// A string shown on the page, with the position of its first char
+#[derive(Debug, Clone)]
+pub struct TextRun{
+    pub x : f64,
+    pub y : f64, // Baseline
+    pub width : f64,
+    pub font_size : f64,
+    pub chars : String,
+    pub(crate) advances : Vec<f64>, // Width of each char in chars
+}
+
 impl Text {
     // Adds a space, given that the last char is not a space
     fn add_space(&mut self){
@@ -31,35 +43,38 @@ impl Text {
 pub(crate) struct Font{
     name : String,
     mapping : HashMap<u32,Vec<u32>>,
+    first_char : u32,
+    widths : Vec<f64>, // Glyph widths from first_char, in thousandths of the font size
 }
 
+// Glyph width used when a font has no widths, in thousandths of the font size
+const DEFAULT_GLYPH_WIDTH : f64 = 500.0;
+
 // 9 Params for text state params (p. 243)
 #[derive(Debug, Clone)]
 pub(crate) struct TextReader {
     y_pos : f64,
+    x_pos : f64, // Start of the line
+    x_offset : f64, // Width of the text shown since the start of the line
     scale : f64,
     graph_scale : f64,
+    graph_x : f64,
     graph_y : f64,
     font_size : f64,
     scaled_font_size : f64,
     leading : f64,
 }
 
-/// Reads the unicode Char Mappings for the fonts on the page
+/// Reads the unicode Char Mappings for the fonts in the page resources
 /// The first font in the result vector is always an empty font
-pub(crate) fn get_page_resources(doc : &mut Document, page_obj : &PdfVar) -> Vec<Font>{
+pub(crate) fn get_page_resources(doc : &mut Document, resource_dict_obj : &PdfVar) -> Vec<Font>{
     let mut fonts : Vec<Font> = Vec::new();
-    fonts.push(Font{name : String::new(), mapping : HashMap::new()});
+    fonts.push(Font{name : String::new(), mapping : HashMap::new(), first_char : 0, widths : Vec::new()});
 
-    // Find resources dictionary
-    let Some(resource_dict_obj) = page_obj.get_dict_value("Resources") else{
-        return fonts;
-    };
-    
     // Retrieve Resources object from ID
     let font_dict_obj = match resource_dict_obj {
-        PdfVar::IndirectObject(obj_id) => {
-            let Some(resource_dict) = doc.get_object_by_id(*obj_id) else{
+        PdfVar::IndirectObject(obj_id, generation) => {
+            let Some(resource_dict) = doc.get_object(*obj_id, *generation) else{
                 return fonts;
             };
             resource_dict
@@ -79,9 +94,9 @@ pub(crate) fn get_page_resources(doc : &mut Document, page_obj : &PdfVar) -> Vec
         PdfVar::Dictionary(x) => {
             x.clone()
         }
-        PdfVar::IndirectObject(x) => {
+        PdfVar::IndirectObject(x, generation) => {
             // Fetch the indirect object
-            let Some(unpacked_indirect_obj) = doc.get_object_by_id(*x) else {
+            let Some(unpacked_indirect_obj) = doc.get_object(*x, *generation) else {
                 return fonts;
             };
             let PdfVar::Object { _id, content } = unpacked_indirect_obj else {
@@ -105,21 +120,21 @@ pub(crate) fn get_page_resources(doc : &mut Document, page_obj : &PdfVar) -> Vec
 
     // Read all fonts
     for (fkey, pdfvar) in all_fonts{ 
-        // Get Object ID for the given font
-        let Some(obj_id) = pdfvar.get_indirect_obj_index() else {
+        // Get Object ID and generation for the given font
+        let Some((obj_id, generation)) = pdfvar.get_object_ref() else {
             continue;
         };
         
         // Retrieve the object with the ID
-        let Some(font_obj) = doc.get_object_by_id(obj_id) else {
+        let Some(font_obj) = doc.get_object(obj_id, generation) else {
             continue;
         };
         
         let mut codex : HashMap<u32, Vec<u32>> = HashMap::new(); 
 
         // Retrieve a ToUnicode
-        if let Some(to_unicode_id) = font_obj.get_dict_int("ToUnicode"){
-            read_to_unicode(doc, &mut codex, to_unicode_id);
+        if let Some(to_unicode_ref) = font_obj.get_dict_value("ToUnicode").and_then(|x| x.get_object_ref()){
+            read_to_unicode(doc, &mut codex, to_unicode_ref);
         };
 
         // Retrieve encoding map
@@ -127,17 +142,24 @@ pub(crate) fn get_page_resources(doc : &mut Document, page_obj : &PdfVar) -> Vec
             read_encoding(doc, &mut codex, encoding_ref);
         };
 
-        fonts.push(Font{name : fkey.to_string(), mapping : codex});
+        // Retrieve glyph widths, used to position the text
+        let first_char = font_obj.get_dict_int("FirstChar").unwrap_or(0) as u32;
+        let mut widths : Vec<f64> = Vec::new();
+        if let Some(PdfVar::Array(width_array)) = font_obj.get_dict_value("Widths").and_then(|x| doc.resolve(x)){
+            widths = width_array.iter().map(|x| x.get_f64().unwrap_or(DEFAULT_GLYPH_WIDTH)).collect();
+        }
+
+        fonts.push(Font{name : fkey.to_string(), mapping : codex, first_char, widths});
     }
     fonts
 }
 
 /// Fetches encoding information
 fn read_encoding(doc : &mut Document, codex : &mut HashMap<u32, Vec<u32>>, encoding_ref : &PdfVar) {
-    let Some(enc_id) = encoding_ref.get_indirect_obj_index() else {
+    let Some((enc_id, enc_gen)) = encoding_ref.get_object_ref() else {
         return;
     };
-    let Some(enc_obj) = doc.get_object_by_id(enc_id) else {        
+    let Some(enc_obj) = doc.get_object(enc_id, enc_gen) else {        
         return;
     };
     let Some(diff_obj) = enc_obj.get_dict_value("Differences") else {
@@ -181,9 +203,9 @@ fn read_encoding(doc : &mut Document, codex : &mut HashMap<u32, Vec<u32>>, encod
 }
 
 /// Parses the ToUnicode object for a font
-fn read_to_unicode(doc : &mut Document, codex : &mut HashMap<u32, Vec<u32>>, to_unicode_id : usize){
+fn read_to_unicode(doc : &mut Document, codex : &mut HashMap<u32, Vec<u32>>, to_unicode_ref : (usize, usize)){
     // Fetch ToUnicode Object
-    let Some(to_unicode_obj) = doc.get_object_by_id(to_unicode_id) else{
+    let Some(to_unicode_obj) = doc.get_object(to_unicode_ref.0, to_unicode_ref.1) else{
         return;
     };
 
@@ -192,8 +214,10 @@ fn read_to_unicode(doc : &mut Document, codex : &mut HashMap<u32, Vec<u32>>, to_
     };
     
     // Add decoded to document
-    doc.it = doc.size();
-    doc.data.extend(to_unicode_content);
+    let Some(start) = doc.append_data(&to_unicode_content, "read ToUnicode map") else{
+        return;
+    };
+    doc.it = start;
 
     loop {
         // Exit when everything is covered
@@ -202,7 +226,7 @@ fn read_to_unicode(doc : &mut Document, codex : &mut HashMap<u32, Vec<u32>>, to_
         }
 
         // Search for unicode mappings
-        if doc.byte() == b'b' {
+        if doc.byte() == Some(b'b') {
             if cmp_u8(&doc.data, doc.it, b"beginbfchar"){
                 doc.it += 11;
                 read_fchar(doc, codex);
@@ -214,6 +238,7 @@ fn read_to_unicode(doc : &mut Document, codex : &mut HashMap<u32, Vec<u32>>, to_
         }
         doc.it += 1;
     }
+    doc.release_data(start, to_unicode_content.len());
 }
 
 /// Reads key-value pairs from beginbfrange-section in ToUnicode, and adds them to the translation map
@@ -223,15 +248,15 @@ fn read_frange(doc : &mut Document, codex : &mut HashMap<u32, Vec<u32>>) -> Opti
         let mut char_range: [u32; 2] = [0,0];
         for i in 0..2{
             doc.skip_whitespace();
-            if doc.byte() != b'<' {
+            if doc.byte()? != b'<' {
                 return Some(());
             }
             doc.it += 1;
             
             // Read the hex-char, can be 2-4 chars
             let mut hex_str : Vec<u8> = Vec::new();
-            while doc.byte().is_ascii_alphanumeric() {
-                hex_str.push(doc.byte());
+            while let Some(byte) = doc.byte().filter(|x| x.is_ascii_alphanumeric()) {
+                hex_str.push(byte);
                 doc.it += 1;
             }
 
@@ -240,7 +265,7 @@ fn read_frange(doc : &mut Document, codex : &mut HashMap<u32, Vec<u32>>) -> Opti
                 return None;
             };
 
-            if doc.byte() != b'>' {
+            if doc.byte()? != b'>' {
                 return None;
             }
             char_range[i] = value;
@@ -251,12 +276,12 @@ fn read_frange(doc : &mut Document, codex : &mut HashMap<u32, Vec<u32>>) -> Opti
 
         let mut ix = char_range[0];
         // Read mapping
-        if doc.byte() == b'['{
+        if doc.byte()? == b'['{
             // Array mapping
             doc.it += 1;
             loop {
                 doc.skip_whitespace();
-                if doc.byte() == b']' {
+                if doc.byte()? == b']' {
                     break;
                 }
                 let Some(v) = read_hex_chars(doc) else {
@@ -265,15 +290,15 @@ fn read_frange(doc : &mut Document, codex : &mut HashMap<u32, Vec<u32>>) -> Opti
                 codex.insert(ix, v);
                 ix += 1;
             }
-        } else if doc.byte() == b'<' {
+        } else if doc.byte()? == b'<' {
             // Range from number mapping
             doc.it += 1;
             doc.skip_whitespace();
-            let Ok(value) = to_hex(&doc.data[doc.it..doc.it+4]) else {
+            let Ok(value) = to_hex(doc.data.get(doc.it..doc.it+4)?) else {
                 return None;
             };
             doc.it += 4;
-            if doc.byte() != b'>' {
+            if doc.byte()? != b'>' {
                 return None;
             }
             doc.it += 1;
@@ -292,15 +317,15 @@ fn read_frange(doc : &mut Document, codex : &mut HashMap<u32, Vec<u32>>) -> Opti
 fn read_fchar(doc : &mut Document, codex : &mut HashMap<u32, Vec<u32>>) -> Option<()>{
     loop {
         doc.skip_whitespace();
-        if doc.byte() != b'<' {
+        if doc.byte()? != b'<' {
             return None;
         }
         doc.it += 1;
         
         // Read the hex-char, can be 2-4 chars
         let mut hex_str : Vec<u8> = Vec::new();
-        while doc.byte().is_ascii_alphanumeric() {
-            hex_str.push(doc.byte());
+        while let Some(byte) = doc.byte().filter(|x| x.is_ascii_alphanumeric()) {
+            hex_str.push(byte);
             doc.it += 1;
         }
 
@@ -309,7 +334,7 @@ fn read_fchar(doc : &mut Document, codex : &mut HashMap<u32, Vec<u32>>) -> Optio
             return None;
         };
         
-        if doc.byte() != b'>' {
+        if doc.byte()? != b'>' {
             return None; 
         }
         doc.it += 1;
@@ -324,20 +349,20 @@ fn read_fchar(doc : &mut Document, codex : &mut HashMap<u32, Vec<u32>>) -> Optio
 /// Reads a hex-string <4*k>, returns u32 vector
 fn read_hex_chars(doc : &mut Document) -> Option<Vec<u32>>{
     doc.skip_whitespace();
-    if doc.byte() != b'<'{
+    if doc.byte()? != b'<'{
         return None;
     }
     doc.it += 1;
 
     let mut chars : Vec<u32> = Vec::new();
     loop {
-        let Ok(num) = to_hex(&doc.data[doc.it..doc.it+4]) else {
+        let Ok(num) = to_hex(doc.data.get(doc.it..doc.it+4)?) else {
             return None;
         };
         chars.push(num);
         doc.it += 4;
         doc.skip_whitespace();
-        if doc.byte() == b'>'{
+        if doc.byte()? == b'>'{
             break;
         }
     }
@@ -346,13 +371,14 @@ fn read_hex_chars(doc : &mut Document) -> Option<Vec<u32>>{
 }
 
 /// Returns a vector of text based on a list of content objects
-pub(crate) fn read_objects_text(doc : &mut Document, obj_ids : Vec<usize>, fonts : &Vec<Font>) -> Option<Vec<Text>>{
+pub(crate) fn read_objects_text(doc : &mut Document, obj_refs : Vec<(usize, usize)>, fonts : &Vec<Font>) -> Option<Vec<Text>>{
     // Iterate over all content objects for the page, store eveything in One Vector
-    let start = doc.size();
+    let mut page_content : Vec<u8> = Vec::new();
 
-    for obj_id in obj_ids{
-        let Some(obj) = doc.get_object_by_id(obj_id) else{
-            return None;
+    for (obj_id, generation) in obj_refs{
+        // A reference to a free object, or to an older generation, is null and has no content
+        let Some(obj) = doc.get_object(obj_id, generation) else{
+            continue;
         };
 
         // Content can be either an array or a dictionary
@@ -364,15 +390,15 @@ pub(crate) fn read_objects_text(doc : &mut Document, obj_ids : Vec<usize>, fonts
             return None;
         };
 
-        if let Some(array) = obj_1.get_usize_array() {
-            for index in array{
-                let Some(objx) = doc.get_object_by_id(index) else {
-                    return None;
+        if let Some(array) = obj_1.get_object_ref_array() {
+            for (index, index_gen) in array{
+                let Some(objx) = doc.get_object(index, index_gen) else {
+                    continue;
                 };
                 let Some(decoded) = objx.get_decoded_stream(doc) else {
                     return None;
                 };
-                doc.data.extend(decoded);
+                page_content.extend(decoded);
             }
             continue;
         }
@@ -380,16 +406,27 @@ pub(crate) fn read_objects_text(doc : &mut Document, obj_ids : Vec<usize>, fonts
         let Some(decoded) = obj.get_decoded_stream(doc) else {
             continue;
         };
-        doc.data.extend(decoded);
+        page_content.extend(decoded);
     }
 
+    // Reading objects can add data to the document, so the content is added last
+    let start = doc.append_data(&page_content, "read page content")?;
+
     doc.it = start;
     // print_raw(&doc.data, doc.it, 100000);
+    let text_objects = parse_content_text(doc, fonts);
+
+    // The content is only needed while it is parsed
+    doc.release_data(start, page_content.len());
+    text_objects
+}
 
+/// Parses the text of page content that has been added to the document, from the current position
+fn parse_content_text(doc : &mut Document, fonts : &Vec<Font>) -> Option<Vec<Text>>{
     let mut text_objects : Vec<Text> = Vec::new();
-    let mut text: Text = Text{pos_y : -1.0, chars : String::new(), scaled_font_size : 0.0, font : String::new(), avg_font_size : 1.0};
+    let mut text: Text = Text{pos_y : -1.0, chars : String::new(), scaled_font_size : 0.0, font : String::new(), avg_font_size : 1.0, runs : Vec::new()};
     let mut text_reader = TextReader{
-        y_pos : 0.0, scale : 1.0, font_size : 1.0, scaled_font_size : 1.0, leading : 0.0, graph_scale : 1.0, graph_y : 0.0
+        y_pos : 0.0, x_pos : 0.0, x_offset : 0.0, scale : 1.0, font_size : 1.0, scaled_font_size : 1.0, leading : 0.0, graph_scale : 1.0, graph_x : 0.0, graph_y : 0.0
     };
     let mut text_reader_stack : Vec<TextReader> = Vec::new();
     text_reader_stack.push(text_reader);
@@ -406,7 +443,7 @@ pub(crate) fn read_objects_text(doc : &mut Document, obj_ids : Vec<usize>, fonts
         let Some(top_graph_state) = text_reader_stack.last_mut() else {
             return None;
         };
-        parse_text_section(doc, &mut text_objects, &mut text, &fonts, top_graph_state)?;
+        parse_text_section(doc, &mut text_objects, &mut text, fonts, top_graph_state)?;
     }
     add_text_section(&mut text, &mut text_objects, 0.0, 0.0);
     Some(text_objects)
@@ -416,13 +453,11 @@ pub(crate) fn read_objects_text(doc : &mut Document, obj_ids : Vec<usize>, fonts
 fn parse_page_content(doc : &mut Document, text_reader_stack : &mut Vec<TextReader>){
     let mut stack : Vec<PdfVar> = Vec::new();
 
-    while doc.it < doc.size() {
-        match doc.byte() {
-            b'B' => {
-                if cmp_u8(&doc.data, doc.it, b"BT"){
-                    doc.it += 2;
-                    return;
-                }
+    while let Some(byte) = doc.byte() {
+        match byte {
+            b'B' if cmp_u8(&doc.data, doc.it, b"BT") => {
+                doc.it += 2;
+                return;
             }
             (48..58) | b'+' | b'-' | b'.' => {
                 obj_parse_numeric(doc, &mut stack);
@@ -438,11 +473,9 @@ fn parse_page_content(doc : &mut Document, text_reader_stack : &mut Vec<TextRead
             b'Q' => {
                 text_reader_stack.pop();
             }
-            b'c' => {
-                if cmp_u8(&doc.data, doc.it, b"cm"){
-                    graph_cm(text_reader_stack, &stack);
-                    doc.it += 1;
-                }
+            b'c' if cmp_u8(&doc.data, doc.it, b"cm") => {
+                graph_cm(text_reader_stack, &stack);
+                doc.it += 1;
             }
             _ => {
             }
@@ -467,9 +500,11 @@ fn graph_cm(text_reader_stack : &mut Vec<TextReader>, stack : &Vec<PdfVar>) -> O
     let Some(new_scale) = scale_obj.get_f64() else{
         return None;
     };
+    let tx = stack.get(4).and_then(|x| x.get_f64()).unwrap_or(0.0);
 
     if let Some(tr) = text_reader_stack.last_mut() {
         tr.graph_scale *= new_scale;
+        tr.graph_x += tx;
         tr.graph_y += ty;
         return Some(());
     }
@@ -480,6 +515,8 @@ fn graph_cm(text_reader_stack : &mut Vec<TextReader>, stack : &Vec<PdfVar>) -> O
 fn parse_text_section(doc : &mut Document, text_objects : &mut Vec<Text>, text : &mut Text, fonts : &Vec<Font>, tr : &mut TextReader) -> Option<()>{    
     tr.scale = 1.0;
     tr.y_pos = 0.0;
+    tr.x_pos = 0.0;
+    tr.x_offset = 0.0;
     tr.leading = 0.0;
     tr.scaled_font_size = tr.font_size;
     
@@ -487,10 +524,10 @@ fn parse_text_section(doc : &mut Document, text_objects : &mut Vec<Text>, text :
 
     loop {
         doc.skip_whitespace();
-        match doc.byte() {
+        match doc.byte()? {
             b'T' => {
                 doc.it += 1;
-                match doc.byte() {
+                match doc.byte()? {
                     b'f' => {
                         text_tf(tr,text, &stack)?;
                     }
@@ -548,8 +585,8 @@ fn parse_text_section(doc : &mut Document, text_objects : &mut Vec<Text>, text :
             }
             _ => {
                 if let Err(e) = parse_object(doc, &mut stack){
-                    match e {
-                        PdfError::UnmatchedChar => {
+                    match e.kind {
+                        ErrorKind::UnmatchedChar => {
                             read_text(doc);
                             stack.clear();
                             doc.it += 1;
@@ -584,6 +621,7 @@ fn text_tj(tr : &mut TextReader, text : &mut Text, text_objects : &mut Vec<Text>
 /// Handles T*
 fn text_asterisk(tr : &mut TextReader) -> Option<()>{
     tr.y_pos += -tr.leading*tr.scale*tr.graph_scale;
+    tr.x_offset = 0.0;
     Some(())
 }
 
@@ -608,7 +646,9 @@ fn text_td(tr : &mut TextReader, text : &mut Text, stack : &Vec<PdfVar>) -> Opti
     }
 
     // Set new value for y-pos, if it is a new BT section position is reset to 0 and then updated
-    tr.y_pos = tr.y_pos + ty * tr.scale * tr.graph_scale;
+    tr.y_pos += ty * tr.scale * tr.graph_scale;
+    tr.x_pos += tx * tr.scale * tr.graph_scale;
+    tr.x_offset = 0.0;
     Some(())
 }
 
@@ -641,6 +681,8 @@ fn text_tm(tr : &mut TextReader, text : &mut Text, stack : &Vec<PdfVar>) -> Opti
     
     tr.scale = new_scale;
     tr.y_pos = ty;
+    tr.x_pos = tx;
+    tr.x_offset = 0.0;
     tr.scaled_font_size = tr.font_size*tr.scale*tr.graph_scale;
     Some(())
 }
@@ -701,6 +743,7 @@ fn text_tj_array(tr : &mut TextReader, text : &mut Text, text_objects : &mut Vec
             if num < -165.0 {
                 text.add_space();
             }
+            tr.x_offset -= num/1000.0*tr.scaled_font_size;
             continue;
         }
         if let PdfVar::StringLiteral(string_lit) = pdfvar {
@@ -712,7 +755,8 @@ fn text_tj_array(tr : &mut TextReader, text : &mut Text, text_objects : &mut Vec
     Some(())
 }
 
-fn add_str_lit(text : &mut Text, tr : &TextReader, string_lit : &Vec<u32>, fonts : &Vec<Font>){
+/// Adds a string to the text section, and moves the position forward by the width of the string
+fn add_str_lit(text : &mut Text, tr : &mut TextReader, string_lit : &Vec<u32>, fonts : &Vec<Font>){
     // Fetch font
     let mut font : &Font = &fonts[0];
     for f in fonts{
@@ -723,17 +767,32 @@ fn add_str_lit(text : &mut Text, tr : &TextReader, string_lit : &Vec<u32>, fonts
     }
 
     let mut s = String::new();
+    let mut advances : Vec<f64> = Vec::new();
     let pre_size = text.chars.len() as f64;
     let mut sum = pre_size*text.avg_font_size;
+    let x_start = tr.x_pos + tr.x_offset + tr.graph_x;
 
     // Iterate over all chars
     for key in string_lit{
         if *key == 0{
             continue;
         }
+
+        // The glyph width moves the position, a code mapped to several chars gets the width on the first char
+        let glyph_width = key.checked_sub(font.first_char)
+            .and_then(|ix| font.widths.get(ix as usize))
+            .copied()
+            .unwrap_or(DEFAULT_GLYPH_WIDTH);
+        let mut advance = glyph_width/1000.0*tr.scaled_font_size;
+        tr.x_offset += advance;
+
         let Some(x_vec) = font.mapping.get(key) else {
-            text.chars.push_str(decode_pdfdoc_char(*key).as_str());
-            s.push_str(decode_pdfdoc_char(*key).as_str());
+            for uc in decode_pdfdoc_char(*key).chars(){
+                s.push(uc);
+                text.chars.push(uc);
+                advances.push(advance);
+                advance = 0.0;
+            }
             continue;
         };
         for x in x_vec{
@@ -742,8 +801,15 @@ fn add_str_lit(text : &mut Text, tr : &TextReader, string_lit : &Vec<u32>, fonts
             };
             s.push(uc);
             text.chars.push(uc);
+            advances.push(advance);
+            advance = 0.0;
         }
     }
+
+    if !s.is_empty(){
+        let width = tr.x_pos + tr.x_offset + tr.graph_x - x_start;
+        text.runs.push(TextRun{x : x_start, y : tr.y_pos + tr.graph_y, width, font_size : tr.scaled_font_size, chars : s.clone(), advances});
+    }
     // !("s {}, {}",s, text.pos_y);
     // Update average font size
     if text.chars.len() > 0 {
@@ -785,6 +851,7 @@ fn add_text_section(text : &mut Text, text_objects : &mut Vec<Text>, y_pos : f64
         copy.font = String::new();
         text_objects.push(copy);
         text.chars.clear();
+        text.runs.clear();
     }
     text.pos_y = y_pos;
     text.scaled_font_size = scaled_font_size;
@@ -794,15 +861,15 @@ fn add_text_section(text : &mut Text, text_objects : &mut Vec<Text>, y_pos : f64
 /// Reads all ascii chars until something else
 fn read_text(doc : &mut Document) -> String{
     let mut output = String::new();
-    loop {
-        if doc.byte().is_ascii_alphabetic(){
-            output.push(doc.byte() as char);
+    while let Some(byte) = doc.byte() {
+        if byte.is_ascii_alphabetic(){
+            output.push(byte as char);
             doc.it += 1;
         }
         else if is_delimiter(&doc.data, doc.it){
             break;
         } else{
-            output.push(doc.byte() as char);
+            output.push(byte as char);
             doc.it += 1;    
         }
     }
@@ -0,0 +1,139 @@
+use std::collections::{HashSet, VecDeque};
+
+use crate::document::Document;
+use crate::pdf_object::PdfVar;
+
+/// Iterator over the (key, value) pairs of a name tree, in key order. Values are resolved
+pub struct NameTree<'a> {
+    doc : &'a mut Document,
+    walker : TreeWalker,
+}
+
+/// Iterator over the (key, value) pairs of a number tree, in key order. Values are resolved
+pub struct NumberTree<'a> {
+    doc : &'a mut Document,
+    walker : TreeWalker,
+}
+
+/// Walks the nodes of a name tree or a number tree, first kid first
+struct TreeWalker {
+    entries_key : &'static str, // Names for name trees, Nums for number trees
+    stack : Vec<(PdfVar, usize)>, // (node, depth), the next node to visit is last
+    visited : HashSet<usize>, // Ids of visited nodes
+    entries : VecDeque<(PdfVar, PdfVar)>, // Unread (key, value) pairs of the current leaf
+}
+
+impl<'a> NameTree<'a> {
+    /// Creates an iterator over the tree with the given root node
+    pub fn new(doc : &'a mut Document, root : &PdfVar) -> Self{
+        NameTree { doc, walker : TreeWalker::new(root, "Names") }
+    }
+}
+
+impl Iterator for NameTree<'_> {
+    type Item = (String, PdfVar);
+
+    fn next(&mut self) -> Option<(String, PdfVar)>{
+        loop {
+            let (key, value) = self.walker.next(self.doc, |_| false)?;
+            let Some(key) = key.get_str() else{
+                continue;
+            };
+            let value = self.doc.resolve(&value).unwrap_or(PdfVar::Null);
+            return Some((key, value));
+        }
+    }
+}
+
+impl<'a> NumberTree<'a> {
+    /// Creates an iterator over the tree with the given root node
+    pub fn new(doc : &'a mut Document, root : &PdfVar) -> Self{
+        NumberTree { doc, walker : TreeWalker::new(root, "Nums") }
+    }
+}
+
+impl Iterator for NumberTree<'_> {
+    type Item = (i64, PdfVar);
+
+    fn next(&mut self) -> Option<(i64, PdfVar)>{
+        loop {
+            let (key, value) = self.walker.next(self.doc, |_| false)?;
+            let PdfVar::Integer { value : key, signed: _ } = key else{
+                continue;
+            };
+            let value = self.doc.resolve(&value).unwrap_or(PdfVar::Null);
+            return Some((key, value));
+        }
+    }
+}
+
+impl TreeWalker {
+    fn new(root : &PdfVar, entries_key : &'static str) -> Self{
+        TreeWalker { entries_key, stack : vec![(root.clone(), 0)], visited : HashSet::new(), entries : VecDeque::new() }
+    }
+
+    /// Returns the next (key, value) pair, the value is not resolved.
+    /// Kids where skip_kid returns true are not visited
+    fn next(&mut self, doc : &mut Document, skip_kid : impl Fn(&PdfVar) -> bool) -> Option<(PdfVar, PdfVar)>{
+        loop {
+            if let Some(entry) = self.entries.pop_front(){
+                return Some(entry);
+            }
+            let (node_ref, depth) = self.stack.pop()?;
+
+            // Nodes deeper than the limit, or already visited through another /Kids entry, are skipped
+            if depth >= doc.limits.max_depth{
+                continue;
+            }
+            if let PdfVar::IndirectObject(node_id, _) = node_ref{
+                if !self.visited.insert(node_id){
+                    continue;
+                }
+            }
+            let Some(node) = doc.resolve(&node_ref) else{
+                continue;
+            };
+
+            // Leaf node, [key1 value1 key2 value2 ...]
+            if let Some(PdfVar::Array(pairs)) = node.get_dict_value(self.entries_key).and_then(|x| doc.resolve(x)){
+                for pair in pairs.chunks_exact(2){
+                    self.entries.push_back((pair[0].clone(), pair[1].clone()));
+                }
+            }
+
+            // Intermediate node, kids are pushed in reverse so the first kid is visited first
+            if let Some(PdfVar::Array(kids)) = node.get_dict_value("Kids").and_then(|x| doc.resolve(x)){
+                for kid_ref in kids.iter().rev(){
+                    let Some(kid) = doc.resolve(kid_ref) else{
+                        continue;
+                    };
+                    if !skip_kid(&kid){
+                        self.stack.push((kid_ref.clone(), depth+1));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Finds the value of a key in a name tree, only the kids where the key is within /Limits are visited
+pub(crate) fn find_in_name_tree(doc : &mut Document, root : &PdfVar, key : &Vec<u32>) -> Option<PdfVar>{
+    let mut walker = TreeWalker::new(root, "Names");
+    let outside_limits = |kid : &PdfVar| {
+        let Some(PdfVar::Array(limits)) = kid.get_dict_value("Limits") else{
+            return false;
+        };
+        let [PdfVar::StringLiteral(low), PdfVar::StringLiteral(high)] = limits.as_slice() else{
+            return false;
+        };
+        key < low || key > high
+    };
+    while let Some((name, value)) = walker.next(doc, outside_limits){
+        if let PdfVar::StringLiteral(name) = name{
+            if &name == key{
+                return Some(value);
+            }
+        }
+    }
+    None
+}
@@ -0,0 +1,212 @@
+/// Metadata read from the XMP packet in the catalogs /Metadata stream
+#[derive(Debug, Clone, Default)]
+pub struct XmpMetadata {
+    pub title : Option<String>, // dc:title, the x-default language if there are several
+    pub creators : Vec<String>, // dc:creator
+    pub doi : Option<String>, // prism:doi
+    pub publication_name : Option<String>, // prism:publicationName
+    pub date : Option<String>, // dc:date
+}
+
+impl XmpMetadata {
+    /// Parses an XMP packet from the decoded bytes of a metadata stream
+    pub fn from_bytes(packet : &[u8]) -> Self{
+        XmpMetadata::parse(&decode_packet(packet))
+    }
+
+    /// Parses the values of an XMP packet
+    pub fn parse(xml : &str) -> Self{
+        let title = get_lang_alt(xml, "dc:title");
+        let creators = get_values(xml, "dc:creator");
+        let doi = get_values(xml, "prism:doi").into_iter().next();
+        let publication_name = get_values(xml, "prism:publicationName").into_iter().next();
+        let date = get_values(xml, "dc:date").into_iter().next();
+        XmpMetadata { title, creators, doi, publication_name, date }
+    }
+
+    /// Returns true if no value was found in the packet
+    pub fn is_empty(&self) -> bool{
+        self.title.is_none() && self.creators.is_empty() && self.doi.is_none()
+            && self.publication_name.is_none() && self.date.is_none()
+    }
+}
+
+/// Decodes the bytes of an XMP packet, UTF-8 unless there is a UTF-16 byte order mark
+fn decode_packet(packet : &[u8]) -> String{
+    if packet.len() >= 2 && (packet[0..2] == [0xFE, 0xFF] || packet[0..2] == [0xFF, 0xFE]){
+        let big_endian = packet[0] == 0xFE;
+        let units : Vec<u16> = packet[2..].chunks_exact(2).map(|x| {
+            if big_endian { u16::from_be_bytes([x[0], x[1]]) } else { u16::from_le_bytes([x[0], x[1]]) }
+        }).collect();
+        return String::from_utf16_lossy(&units);
+    }
+    let packet = packet.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(packet);
+    String::from_utf8_lossy(packet).to_string()
+}
+
+/// Returns the values of a property, the list items of an rdf container or the text of a simple property.
+/// Properties can also be written as attributes of rdf:Description
+fn get_values(xml : &str, name : &str) -> Vec<String>{
+    if let Some(content) = get_element(xml, name){
+        let items = get_elements(content, "rdf:li");
+        if items.is_empty(){
+            return non_empty(get_text(content)).into_iter().collect();
+        }
+        return items.iter().filter_map(|(_, item)| non_empty(get_text(item))).collect();
+    }
+    get_attribute(xml, name).and_then(non_empty).into_iter().collect()
+}
+
+/// Returns the value of a language alternative, the x-default value is preferred
+fn get_lang_alt(xml : &str, name : &str) -> Option<String>{
+    let Some(content) = get_element(xml, name) else{
+        return get_attribute(xml, name).and_then(non_empty);
+    };
+    let items = get_elements(content, "rdf:li");
+    if items.is_empty(){
+        return non_empty(get_text(content));
+    }
+    if let Some((_, item)) = items.iter().find(|(tag, _)| get_attribute(tag, "xml:lang").as_deref() == Some("x-default")){
+        if let Some(value) = non_empty(get_text(item)){
+            return Some(value);
+        }
+    }
+    items.iter().find_map(|(_, item)| non_empty(get_text(item)))
+}
+
+/// Returns the content of the first element with the given name
+fn get_element<'a>(xml : &'a str, name : &str) -> Option<&'a str>{
+    get_elements(xml, name).into_iter().next().map(|(_, content)| content)
+}
+
+/// Returns the start tags and contents of all elements with the given name. Nested elements with the same name are not supported
+fn get_elements<'a>(xml : &'a str, name : &str) -> Vec<(&'a str, &'a str)>{
+    let mut elements : Vec<(&str, &str)> = Vec::new();
+    let start_pattern = format!("<{}", name);
+    let end_pattern = format!("</{}>", name);
+    let mut ix = 0;
+
+    while let Some(found) = xml[ix..].find(&start_pattern){
+        let tag_start = ix + found;
+        let name_end = tag_start + start_pattern.len();
+        ix = name_end;
+
+        // The name has to end here, <dc:titles> is another element
+        let Some(next) = xml[name_end..].chars().next() else{
+            break;
+        };
+        if !(next == '>' || next == '/' || next.is_whitespace()){
+            continue;
+        }
+        let Some(tag_len) = xml[tag_start..].find('>') else{
+            break;
+        };
+        let tag_end = tag_start + tag_len + 1;
+        let tag = &xml[tag_start..tag_end];
+        ix = tag_end;
+
+        // Empty element
+        if tag.ends_with("/>"){
+            elements.push((tag, ""));
+            continue;
+        }
+        let Some(content_len) = xml[tag_end..].find(&end_pattern) else{
+            break;
+        };
+        elements.push((tag, &xml[tag_end..tag_end+content_len]));
+        ix = tag_end + content_len + end_pattern.len();
+    }
+    elements
+}
+
+/// Returns the value of an attribute, name="value" or name='value'
+fn get_attribute(xml : &str, name : &str) -> Option<String>{
+    let pattern = format!("{}=", name);
+    let mut ix = 0;
+    while let Some(found) = xml[ix..].find(&pattern){
+        let start = ix + found;
+        ix = start + pattern.len();
+
+        // Has to be a whole attribute name
+        if !xml[..start].ends_with(|c : char| c.is_whitespace()){
+            continue;
+        }
+        let Some(quote) = xml[ix..].chars().next().filter(|c| *c == '"' || *c == '\'') else{
+            continue;
+        };
+        let value_start = ix + 1;
+        let value_len = xml[value_start..].find(quote)?;
+        return Some(unescape(&xml[value_start..value_start+value_len]));
+    }
+    None
+}
+
+/// Returns the text of an element content, without tags
+fn get_text(content : &str) -> String{
+    let mut text = String::new();
+    let mut rest = content;
+    while let Some(tag_start) = rest.find('<'){
+        // CDATA sections are text
+        if rest[tag_start..].starts_with("<![CDATA["){
+            text.push_str(&unescape(&rest[..tag_start]));
+            let cdata = &rest[tag_start+9..];
+            let end = cdata.find("]]>").unwrap_or(cdata.len());
+            text.push_str(&cdata[..end]);
+            rest = cdata.get(end+3..).unwrap_or("");
+            continue;
+        }
+        text.push_str(&unescape(&rest[..tag_start]));
+        let Some(tag_len) = rest[tag_start..].find('>') else{
+            rest = "";
+            break;
+        };
+        rest = &rest[tag_start+tag_len+1..];
+    }
+    text.push_str(&unescape(rest));
+    text
+}
+
+/// Replaces the XML entities and character references
+fn unescape(text : &str) -> String{
+    let mut output = String::new();
+    let mut rest = text;
+    while let Some(amp) = rest.find('&'){
+        output.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let Some(end) = rest.find(';') else{
+            break;
+        };
+        let entity = &rest[1..end];
+        let chr = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ if entity.starts_with("#x") => u32::from_str_radix(&entity[2..], 16).ok().and_then(char::from_u32),
+            _ if entity.starts_with('#') => entity[1..].parse::<u32>().ok().and_then(char::from_u32),
+            _ => None,
+        };
+        match chr {
+            Some(c) => {
+                output.push(c);
+                rest = &rest[end+1..];
+            }
+            None => {
+                output.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+/// Collapses whitespace, empty values are None
+fn non_empty(value : String) -> Option<String>{
+    let value = value.split_whitespace().collect::<Vec<&str>>().join(" ");
+    if value.is_empty(){
+        return None;
+    }
+    Some(value)
+}
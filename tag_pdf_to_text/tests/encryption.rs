@@ -0,0 +1,31 @@
+use tag_pdf_to_text::load_pdf_doc;
+
+fn fixture(name : &str) -> String{
+    format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+/// Opens a document encrypted with the empty user password, /O /U and the strings are written as escaped literal strings
+fn check_literal_strings(name : &str){
+    let mut doc = load_pdf_doc(&fixture(name)).unwrap();
+    assert!(doc.is_encrypted());
+    assert!(!doc.is_locked());
+    assert_eq!(doc.get_info("Title").as_deref(), Some("A Study of Interesting Things in Science"));
+    assert_eq!(doc.get_info("Author").as_deref(), Some("Jane Doe and John Roe"));
+    let text = doc.get_text_from_page(0).unwrap();
+    assert!(text.iter().any(|x| x.chars.contains("Interesting Things")));
+}
+
+#[test]
+fn rc4_r2_literal_strings(){
+    check_literal_strings("encrypted_literal_r2.pdf");
+}
+
+#[test]
+fn rc4_r3_literal_strings(){
+    check_literal_strings("encrypted_literal_r3.pdf");
+}
+
+#[test]
+fn aes_r4_literal_strings(){
+    check_literal_strings("encrypted_literal_r4.pdf");
+}
@@ -0,0 +1,24 @@
+use tag_pdf_to_text::load_pdf_doc;
+
+fn fixture(name : &str) -> String{
+    format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+#[test]
+fn truncated_file_is_rebuilt(){
+    // The file ends inside the info dictionary, the xref table and trailer are missing
+    let mut doc = load_pdf_doc(&fixture("truncated.pdf")).unwrap();
+    assert!(doc.is_recovered());
+    assert_eq!(doc.page_count(), 1);
+    let text = doc.get_text_from_page(0).unwrap();
+    assert!(text.iter().any(|x| x.chars.contains("Interesting Things")));
+}
+
+#[test]
+fn truncated_startxref_is_read(){
+    // The file ends right after the startxref offset
+    let mut doc = load_pdf_doc(&fixture("truncated_startxref.pdf")).unwrap();
+    assert!(!doc.is_recovered());
+    assert_eq!(doc.page_count(), 1);
+    assert_eq!(doc.get_info("Title").as_deref(), Some("A Study of Interesting Things in Science"));
+}